@@ -1,6 +1,12 @@
 
+use std::env;
 use std::io::*;
 use std::process;
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 
 
 pub fn run(cmd: &str, arguments: &[&str]) -> String {
@@ -18,6 +24,20 @@ pub fn run(cmd: &str, arguments: &[&str]) -> String {
 }
 
 
+pub fn list_status(cmd: &str, arguments: &[&str]) -> (bool, Vec<String>) {
+
+    let mut command = process::Command::new(cmd);
+    let result = command
+        .args(arguments)
+        .output()
+        .expect("Failure running command: commands::list_status()");
+
+    let output = format!("{}{}", String::from_utf8_lossy(&result.stdout), String::from_utf8_lossy(&result.stderr));
+    let lines = output.lines().map(|s: &str| s.to_string()).collect::<Vec<String>>();
+
+    (result.status.success(), lines)
+}
+
 pub fn list(cmd: &str, arguments: &[&str]) -> Vec<String> {
 
     let output = run(cmd, arguments);
@@ -28,35 +48,496 @@ pub fn list(cmd: &str, arguments: &[&str]) -> Vec<String> {
         .collect::<Vec<String>>()
 }
 
-pub fn piped(first_cmd: &str, first_args: Vec<&str>,
-             second_cmd: &str, second_args: Vec<&str>) -> Vec<String> {
+pub fn zfs_destroy_arguments<'a>(target: &'a str, flag: &'a str) -> Vec<&'a str> {
+
+    let mut arguments = vec!["destroy"];
+    if !flag.is_empty() { arguments.push(flag); }
+    arguments.push(target);
+
+    arguments
+}
+
+pub fn zfs_destroy(target: &str, flag: &str) -> Vec<String> {
+    list("zfs", &zfs_destroy_arguments(target, flag))
+}
+
+pub fn zfs_snapshot(new_snapshot: &str, is_recursive: bool, properties: &[String]) -> Vec<String> {
+
+    let mut arguments = vec!["snapshot"];
+    if is_recursive { arguments.push("-r"); }
+    for property in properties.iter() {
+        arguments.push("-o");
+        arguments.push(property);
+    }
+    arguments.push(new_snapshot);
+
+    list("zfs", &arguments)
+}
+
+pub fn expand_template(template: &str) -> String {
+
+    let hostname = run("hostname", &["-s"]).trim().to_string();
+    let user = env::var("SUDO_USER").or_else(|_| env::var("USER")).unwrap_or_default();
+
+    let template = template
+        .replace("{hostname}", &hostname)
+        .replace("{user}", &user);
+
+    run("date", &[&format!("+{}", template)]).trim().to_string()
+}
+
+const CHUNK_SIZE: usize = 128 * 1024;
+
+pub struct Stage {
+    command:     String,
+    child:       process::Child,
+    status:      Option<Result<process::ExitStatus>>,
+    output:      Arc<Mutex<Vec<String>>>,
+    readers:     Vec<thread::JoinHandle<()>>,
+}
+
+struct Buffer {
+    chunks:   Mutex<(VecDeque<Vec<u8>>, bool)>,
+    changed:  Condvar,
+    buffered: AtomicU64,
+    size:     AtomicU64,
+}
+
+pub struct Pipeline {
+    stages:      Vec<Stage>,
+    transferred: Arc<AtomicU64>,
+    rate_limit:  Arc<AtomicU64>,
+    buffer:      Arc<Buffer>,
+    started:     Instant,
+}
+
+impl Pipeline {
+
+    pub fn transferred(&self) -> u64 {
+        self.transferred.load(Ordering::Relaxed)
+    }
+
+    pub fn buffered(&self) -> u64 {
+        self.buffer.buffered.load(Ordering::Relaxed)
+    }
+
+    pub fn rate_limit(&self) -> u64 {
+        self.rate_limit.load(Ordering::Relaxed)
+    }
+
+    pub fn set_rate_limit(&self, rate_limit: u64) {
+        self.rate_limit.store(rate_limit, Ordering::Relaxed);
+    }
+
+    pub fn buffer_size(&self) -> u64 {
+        self.buffer.size.load(Ordering::Relaxed)
+    }
+
+    pub fn set_buffer_size(&self, buffer_size: u64) {
+        self.buffer.size.store(buffer_size.max(CHUNK_SIZE as u64), Ordering::Relaxed);
+        self.buffer.changed.notify_all();
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn is_running(&mut self) -> bool {
+
+        for stage in self.stages.iter_mut().filter(|stage| stage.status.is_none()) {
+            stage.status = stage.child.try_wait().transpose();
+        }
+
+        self.stages.iter().any(|stage| stage.status.is_none())
+    }
+
+    pub fn cancel(&mut self) -> Result<()> {
+
+        for stage in self.stages.iter_mut().filter(|stage| stage.status.is_none()) {
+            let _ = stage.child.kill();
+            stage.status = Some(stage.child.wait());
+        }
+
+        for stage in self.stages.iter_mut() {
+            for reader in stage.readers.drain(..) {
+                let _ = reader.join();
+            }
+        }
+
+        match self.stages.iter().find_map(|stage| stage.status.as_ref().and_then(|status| status.as_ref().err())) {
+            Some(error) => Err(Error::new(error.kind(), error.to_string())),
+            None        => Ok(()),
+        }
+    }
+
+    pub fn report(&mut self) -> Vec<String> {
+
+        let _ = self.cancel();
+
+        let mut report = Vec::new();
+        for stage in self.stages.iter() {
+            let status = match &stage.status {
+                Some(Ok(status)) => status.to_string(),
+                Some(Err(error)) => format!("wait failed: {}", error),
+                None             => String::from("unknown status"),
+            };
+            report.push(format!("{}: {}", stage.command, status));
+            report.extend(stage.output.lock().unwrap().iter().map(|line| format!("    {}", line)));
+        }
+
+        report
+    }
+
+    pub fn output(&self) -> Vec<String> {
+        self.stages.last().map(|stage| stage.output.lock().unwrap().clone()).unwrap_or_default()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.stages.iter().all(|stage| matches!(stage.status, Some(Ok(status)) if status.success()))
+    }
+}
+
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        let _ = self.cancel();
+    }
+}
+
+fn spawn_stage(cmd: &str, arguments: &[&str], stdin: process::Stdio) -> Result<Stage> {
 
-    let mut first_command = process::Command::new(first_cmd)
-        .args(first_args)
+    let mut child = process::Command::new(cmd)
+        .args(arguments)
+        .stdin(stdin)
         .stdout(process::Stdio::piped())
-        .spawn()
-        .unwrap();
+        .stderr(process::Stdio::piped())
+        .spawn()?;
 
-    let mut second_command = process::Command::new(second_cmd)
-        .args(second_args)
-        .stdin(process::Stdio::piped())
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let readers = child.stderr.take().map(|stderr| read_lines(stderr, Arc::clone(&output))).into_iter().collect();
+
+    let command = format!("{} {}", cmd, arguments.join(" ")).trim().to_string();
+
+    Ok(Stage { command, child, status: None, output, readers })
+}
+
+const DOUBLE_QUOTE_ESCAPES: [char; 5] = ['$', '`', '"', '\\', '\n'];
+
+pub fn split_pipeline(line: &str) -> std::result::Result<Vec<Vec<String>>, String> {
+
+    let mut stages = vec![Vec::new()];
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some('\''), '\'')        => { quote = None; },
+            (Some('\''), _)           => { word.get_or_insert_with(String::new).push(c); },
+            (Some('"'), '"')          => { quote = None; },
+            (Some('"'), '\\')         => {
+                let word = word.get_or_insert_with(String::new);
+                match chars.peek() {
+                    Some(&next) if DOUBLE_QUOTE_ESCAPES.contains(&next) => { word.push(next); chars.next(); },
+                    _ => { word.push(c); },
+                }
+            },
+            (None, '\\')              => {
+                let escaped = chars.next().ok_or("Trailing backslash")?;
+                word.get_or_insert_with(String::new).push(escaped);
+            },
+            (Some(_), _)              => { word.get_or_insert_with(String::new).push(c); },
+            (None, '\'') | (None, '"') => { quote = Some(c); word.get_or_insert_with(String::new); },
+            (None, '|')               => {
+                stages.last_mut().unwrap().extend(word.take());
+                stages.push(Vec::new());
+            },
+            (None, c) if c.is_whitespace() => { stages.last_mut().unwrap().extend(word.take()); },
+            (None, _)                 => { word.get_or_insert_with(String::new).push(c); },
+        }
+    }
+
+    if let Some(quote) = quote {
+        return Err(format!("Unterminated {} quote", quote));
+    }
+    stages.last_mut().unwrap().extend(word.take());
+
+    if stages.iter().any(|stage| stage.is_empty()) {
+        return Err(String::from("Empty command in pipeline"));
+    }
+
+    Ok(stages)
+}
+
+pub fn quote(word: &str) -> String {
+    format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub fn piped(first_cmd: &str, first_args: Vec<&str>, stages: &[Vec<String>],
+             rate_limit: u64, buffer_size: u64) -> Result<Pipeline> {
+
+    let mut first = spawn_stage(first_cmd, &first_args, process::Stdio::null())?;
+    let mut spawned: Vec<Stage> = Vec::new();
+
+    for stage in stages {
+        let stdin = match spawned.last_mut().and_then(|previous| previous.child.stdout.take()) {
+            Some(stdout) => process::Stdio::from(stdout),
+            None         => process::Stdio::piped(),
+        };
+        let arguments = stage[1..].iter().map(|s| s.as_str()).collect::<Vec<&str>>();
+
+        match spawn_stage(&stage[0], &arguments, stdin) {
+            Ok(stage)  => { spawned.push(stage); },
+            Err(error) => {
+                for stage in std::iter::once(&mut first).chain(spawned.iter_mut()) {
+                    let _ = stage.child.kill();
+                    let _ = stage.child.wait();
+                }
+                return Err(Error::new(error.kind(), format!("{}: {}", stage[0], error)));
+            },
+        }
+    }
+
+    if let Some(last) = spawned.last_mut() {
+        if let Some(stdout) = last.child.stdout.take() {
+            let reader = read_lines(stdout, Arc::clone(&last.output));
+            last.readers.push(reader);
+        }
+    }
+
+    let transferred = Arc::new(AtomicU64::new(0));
+    let rate_limit = Arc::new(AtomicU64::new(rate_limit));
+    let buffer = Arc::new(Buffer {
+        chunks:   Mutex::new((VecDeque::new(), false)),
+        changed:  Condvar::new(),
+        buffered: AtomicU64::new(0),
+        size:     AtomicU64::new(buffer_size.max(CHUNK_SIZE as u64)),
+    });
+
+    let sink = spawned.first_mut().and_then(|second| second.child.stdin.take());
+    if let (Some(source), Some(sink)) = (first.child.stdout.take(), sink) {
+        fill_buffer(source, Arc::clone(&buffer));
+        drain_buffer(sink, Arc::clone(&buffer), Arc::clone(&transferred), Arc::clone(&rate_limit));
+    }
+
+    let mut stages = vec![first];
+    stages.extend(spawned);
+
+    Ok(Pipeline { stages, transferred, rate_limit, buffer, started: Instant::now() })
+}
+
+fn fill_buffer<R: Read + Send + 'static>(mut source: R, buffer: Arc<Buffer>) {
+
+    thread::spawn(move || {
+        loop {
+            let mut chunk = vec![0u8; CHUNK_SIZE];
+            let size = match source.read(&mut chunk) {
+                Ok(0)    => { break; },
+                Ok(size) => { size },
+                Err(ref error) if error.kind() == ErrorKind::Interrupted => { continue; },
+                Err(_)   => { break; },
+            };
+            chunk.truncate(size);
+
+            let mut chunks = buffer.chunks.lock().unwrap();
+            while !chunks.1 && buffer.buffered.load(Ordering::Relaxed) + size as u64 > buffer.size.load(Ordering::Relaxed) {
+                chunks = buffer.changed.wait(chunks).unwrap();
+            }
+            if chunks.1 { return; }
+
+            chunks.0.push_back(chunk);
+            buffer.buffered.fetch_add(size as u64, Ordering::Relaxed);
+            buffer.changed.notify_all();
+        }
+
+        buffer.chunks.lock().unwrap().1 = true;
+        buffer.changed.notify_all();
+    });
+}
+
+fn drain_buffer<W: Write + Send + 'static>(mut sink: W, buffer: Arc<Buffer>,
+                                           transferred: Arc<AtomicU64>, rate_limit: Arc<AtomicU64>) {
+
+    thread::spawn(move || {
+        let mut window = (Instant::now(), 0u64, 0u64);
+
+        loop {
+            let chunk = {
+                let mut chunks = buffer.chunks.lock().unwrap();
+                while chunks.0.is_empty() && !chunks.1 {
+                    chunks = buffer.changed.wait(chunks).unwrap();
+                }
+                match chunks.0.pop_front() {
+                    Some(chunk) => chunk,
+                    None        => break,
+                }
+            };
+            buffer.buffered.fetch_sub(chunk.len() as u64, Ordering::Relaxed);
+            buffer.changed.notify_all();
+
+            let limit = rate_limit.load(Ordering::Relaxed);
+            if limit != window.2 {
+                window = (Instant::now(), 0, limit);
+            }
+            if limit > 0 {
+                let allowed = Duration::from_secs_f64(window.1 as f64 / limit as f64);
+                if let Some(delay) = allowed.checked_sub(window.0.elapsed()) {
+                    thread::sleep(delay);
+                }
+            }
+
+            if sink.write_all(&chunk).is_err() {
+                break;
+            }
+            window.1 += chunk.len() as u64;
+            transferred.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+        }
+
+        let mut chunks = buffer.chunks.lock().unwrap();
+        chunks.0.clear();
+        chunks.1 = true;
+        buffer.buffered.store(0, Ordering::Relaxed);
+        buffer.changed.notify_all();
+    });
+}
+
+pub const LOG_FILE: &str = "/var/log/zc.log";
+
+pub fn log(message: &str) {
+
+    let timestamp = run("date", &["+%Y-%m-%d %H:%M:%S"]);
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(LOG_FILE);
+
+    if let Ok(mut file) = file {
+        let _ = writeln!(file, "{} {}", timestamp.trim(), message);
+    }
+}
+
+pub struct Follower {
+    child: process::Child,
+    lines: Arc<Mutex<Vec<String>>>,
+}
+
+impl Follower {
+
+    pub fn take(&self) -> Vec<String> {
+        let mut lines = self.lines.lock().unwrap();
+        lines.drain(..).collect::<Vec<String>>()
+    }
+}
+
+impl Drop for Follower {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+pub fn follow(cmd: &str, arguments: &[&str]) -> Follower {
+
+    let mut child = process::Command::new(cmd)
+        .args(arguments)
         .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
         .spawn()
-        .unwrap();
+        .expect("Failure running command: commands::follow()");
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+
+    if let Some(stdout) = child.stdout.take() {
+        read_lines(stdout, Arc::clone(&lines));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        read_lines(stderr, Arc::clone(&lines));
+    }
+
+    Follower { child, lines }
+}
 
-    if let Some(ref mut stdout) = first_command.stdout {
-        if let Some(ref mut stdin) = second_command.stdin {
-            let mut buf: Vec<u8> = Vec::new();
-            stdout.read_to_end(&mut buf).unwrap();
-            stdin.write_all(&buf).unwrap();
+fn read_lines<R: Read + Send + 'static>(source: R, lines: Arc<Mutex<Vec<String>>>) -> thread::JoinHandle<()> {
+
+    thread::spawn(move || {
+        for line in BufReader::new(source).lines() {
+            match line {
+                Ok(line) => { lines.lock().unwrap().push(line); },
+                Err(_)   => { break; },
+            }
         }
-    }   
+    })
+}
 
-    let output = second_command.wait_with_output().unwrap().stdout;
+#[cfg(test)]
+mod tests {
 
-    String::from_utf8_lossy(&output)
-        .to_string()
-        .lines()
-        .map(|s: &str| s.to_string())
-        .collect::<Vec<String>>()
-}
\ No newline at end of file
+    use super::*;
+
+    fn words(stage: &[&str]) -> Vec<String> {
+        stage.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn split_pipeline_splits_stages_on_bars() {
+        let stages = split_pipeline("zstd -3 | ssh host zfs recv -s tank/x").unwrap();
+        assert_eq!(stages, vec![words(&["zstd", "-3"]), words(&["ssh", "host", "zfs", "recv", "-s", "tank/x"])]);
+    }
+
+    #[test]
+    fn split_pipeline_groups_quoted_words() {
+        let stages = split_pipeline(r#"dd "of=/mnt/a b" 'x  y' ""x"#).unwrap();
+        assert_eq!(stages, vec![words(&["dd", "of=/mnt/a b", "x  y", "x"])]);
+    }
+
+    #[test]
+    fn split_pipeline_keeps_empty_quoted_words() {
+        let stages = split_pipeline(r#"echo "" ''"#).unwrap();
+        assert_eq!(stages, vec![words(&["echo", "", ""])]);
+    }
+
+    #[test]
+    fn split_pipeline_keeps_bars_inside_quotes() {
+        let stages = split_pipeline(r#"ssh host "zfs send a | zfs recv b" | cat 'x|y'"#).unwrap();
+        assert_eq!(stages, vec![words(&["ssh", "host", "zfs send a | zfs recv b"]), words(&["cat", "x|y"])]);
+    }
+
+    #[test]
+    fn split_pipeline_escapes_outside_quotes() {
+        let stages = split_pipeline(r#"echo a\ b \| \"c\\"#).unwrap();
+        assert_eq!(stages, vec![words(&["echo", "a b", "|", "\"c\\"])]);
+    }
+
+    #[test]
+    fn split_pipeline_escapes_only_special_characters_in_double_quotes() {
+        let stages = split_pipeline(r#"echo "a\"b\\c\$d\`e" "\n\x""#).unwrap();
+        assert_eq!(stages, vec![words(&["echo", "a\"b\\c$d`e", "\\n\\x"])]);
+    }
+
+    #[test]
+    fn split_pipeline_keeps_backslashes_in_single_quotes() {
+        let stages = split_pipeline(r#"echo 'a\b\'"#).unwrap();
+        assert_eq!(stages, vec![words(&["echo", "a\\b\\"])]);
+    }
+
+    #[test]
+    fn split_pipeline_rejects_empty_stages() {
+        assert!(split_pipeline("").is_err());
+        assert!(split_pipeline("a | | b").is_err());
+        assert!(split_pipeline("a |").is_err());
+        assert!(split_pipeline("| a").is_err());
+    }
+
+    #[test]
+    fn split_pipeline_rejects_unterminated_quotes() {
+        assert_eq!(split_pipeline("a 'b"), Err(String::from("Unterminated ' quote")));
+        assert_eq!(split_pipeline(r#"a "b"#), Err(String::from("Unterminated \" quote")));
+        assert!(split_pipeline(r#"a \"#).is_err());
+    }
+
+    #[test]
+    fn quote_round_trips_through_split_pipeline() {
+        let word = r#"/mnt/x y/"q" \ $z."#;
+        let stages = split_pipeline(&format!("split - {}", quote(word))).unwrap();
+        assert_eq!(stages, vec![words(&["split", "-", word])]);
+    }
+}
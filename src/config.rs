@@ -0,0 +1,136 @@
+use std::fs;
+
+pub const CONFIG_FILE: &str = "/etc/zc.conf";
+
+pub struct Config {
+    pub capacity_warning:      u64,
+    pub fragmentation_warning: u64,
+    pub scrub_age_warning:     u64,
+    pub snapshot_template:     String,
+    pub policies:              Vec<String>,
+    pub send_rate_limit:       u64,
+    pub send_buffer_size:      u64,
+}
+
+impl Config {
+
+    pub fn load() -> Config {
+
+        let mut config = Config {
+            capacity_warning:      80,
+            fragmentation_warning: 50,
+            scrub_age_warning:     30,
+            snapshot_template:     String::from("manual-%Y%m%d-%H%M"),
+            policies:              Vec::new(),
+            send_rate_limit:       0,
+            send_buffer_size:      64 * 1024 * 1024,
+        };
+
+        let contents = fs::read_to_string(CONFIG_FILE).unwrap_or_default();
+
+        for (key, value) in entries(&contents) {
+            match key.as_str() {
+                "capacity_warning"      => { config.capacity_warning      = value.parse().unwrap_or(config.capacity_warning);      },
+                "fragmentation_warning" => { config.fragmentation_warning = value.parse().unwrap_or(config.fragmentation_warning); },
+                "scrub_age_warning"     => { config.scrub_age_warning     = value.parse().unwrap_or(config.scrub_age_warning);     },
+                "snapshot_template"     => { config.snapshot_template     = value; },
+                "policy"                => { config.policies.push(value); },
+                "send_rate_limit"       => { config.send_rate_limit       = parse_size(&value).unwrap_or(config.send_rate_limit);  },
+                "send_buffer_size"      => { config.send_buffer_size      = parse_size(&value).unwrap_or(config.send_buffer_size); },
+                _ => { },
+            }
+        }
+
+        config
+    }
+}
+
+fn entries(contents: &str) -> Vec<(String, String)> {
+
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+pub fn parse_size(value: &str) -> Option<u64> {
+
+    let value = value.trim().to_uppercase();
+    let value = value.trim_end_matches('B');
+
+    let (number, multiplier) = match value.chars().last() {
+        Some('K') => (&value[..value.len() - 1], 1_u64 << 10),
+        Some('M') => (&value[..value.len() - 1], 1_u64 << 20),
+        Some('G') => (&value[..value.len() - 1], 1_u64 << 30),
+        Some('T') => (&value[..value.len() - 1], 1_u64 << 40),
+        Some('P') => (&value[..value.len() - 1], 1_u64 << 50),
+        _         => (value, 1_u64),
+    };
+
+    let number = number.trim();
+    if !number.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+
+    number.parse::<f64>().ok()
+        .map(|number| number * multiplier as f64)
+        .filter(|size| size.is_finite() && *size < u64::MAX as f64)
+        .map(|size| size as u64)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::contents::human_size;
+
+    #[test]
+    fn parse_size_reads_plain_bytes() {
+        assert_eq!(parse_size("0"), Some(0));
+        assert_eq!(parse_size("0B"), Some(0));
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size(" 512B "), Some(512));
+    }
+
+    #[test]
+    fn parse_size_reads_suffixes() {
+        assert_eq!(parse_size("2K"), Some(2 << 10));
+        assert_eq!(parse_size("2k"), Some(2 << 10));
+        assert_eq!(parse_size("10M"), Some(10 << 20));
+        assert_eq!(parse_size("64MB"), Some(64 << 20));
+        assert_eq!(parse_size("3G"), Some(3 << 30));
+        assert_eq!(parse_size("1T"), Some(1 << 40));
+        assert_eq!(parse_size("2P"), Some(2 << 50));
+    }
+
+    #[test]
+    fn parse_size_reads_decimals() {
+        assert_eq!(parse_size("64.0M"), Some(64 << 20));
+        assert_eq!(parse_size("1.5G"), Some(3 << 29));
+        assert_eq!(parse_size("0.5K"), Some(512));
+    }
+
+    #[test]
+    fn parse_size_rejects_invalid_values() {
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("M"), None);
+        assert_eq!(parse_size("fast"), None);
+        assert_eq!(parse_size("-1M"), None);
+        assert_eq!(parse_size("10X"), None);
+        assert_eq!(parse_size("inf"), None);
+        assert_eq!(parse_size("NaN"), None);
+        assert_eq!(parse_size("1e3"), None);
+        assert_eq!(parse_size("1e30"), None);
+        assert_eq!(parse_size("16384P"), None);
+        assert_eq!(parse_size("99999999999999999999"), None);
+    }
+
+    #[test]
+    fn parse_size_reads_human_size_output() {
+        for size in [0, 512, 1 << 10, 64 << 20, 3 << 29, 1 << 40, 1 << 50] {
+            assert_eq!(parse_size(&human_size(size)), Some(size), "{}", human_size(size));
+        }
+    }
+}
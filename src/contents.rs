@@ -1,11 +1,19 @@
 
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
 use crate::dialogs;
+use crate::retention;
+
+const DETAILS_INTERVAL: Duration = Duration::from_secs(30);
 
 pub enum ContentType {
     Pools,
     Datasets,
     Volumes,
     Snapshots,
+    Bookmarks,
 }
 
 pub struct Content {
@@ -14,6 +22,13 @@ pub struct Content {
     pub position:     i32,
     pub start:        i32,
     pub list:         Vec<String>,
+    pub details:      Vec<String>,
+    pub marked:       Vec<String>,
+    pub filter:       String,
+    pub is_grouped:   bool,
+    prefixes:         Vec<String>,
+    detail_cache:     HashMap<String, String>,
+    detail_time:      Option<Instant>,
 }
 
 impl Content {
@@ -26,6 +41,13 @@ impl Content {
             position: 0,
             start: 0,
             list: Vec::new(),
+            details: Vec::new(),
+            marked: Vec::new(),
+            filter: String::new(),
+            is_grouped: false,
+            prefixes: Vec::new(),
+            detail_cache: HashMap::new(),
+            detail_time: None,
         }
     }
 
@@ -36,7 +58,79 @@ impl Content {
             ContentType::Datasets  => { self.list = zfs::dataset_list(); },
             ContentType::Volumes   => { self.list = zfs::volume_list(); },
             ContentType::Snapshots => { self.list = zfs::snapshot_list(); },
+            ContentType::Bookmarks => { self.list = zfs::bookmark_list(); },
+        }
+
+        self.update_details();
+
+        if let ContentType::Snapshots = self.content_type {
+            self.filter_snapshots();
+        }
+
+        let list = &self.list;
+        self.marked.retain(|name| list.contains(name));
+    }
+
+    fn update_details(&mut self) {
+
+        let is_expired = match self.detail_time {
+            Some(time) => time.elapsed() > DETAILS_INTERVAL,
+            None       => true,
+        };
+        let is_missing = self.list.iter().any(|name| !self.detail_cache.contains_key(name));
+
+        if is_expired || is_missing {
+            let details = match self.content_type {
+                ContentType::Pools     => { self.list.iter().map(|p| zpool::pool_summary(p)).collect() },
+                ContentType::Snapshots => {
+                    let policies = retention::policies();
+                    self.prefixes = retention::prefixes(&policies);
+                    zfs::snapshot_details(&self.list, &policies, &self.prefixes)
+                },
+                _                      => { vec![String::new(); self.list.len()] },
+            };
+
+            self.detail_cache = self.list.iter().cloned().zip(details).collect();
+            self.detail_time = Some(Instant::now());
+        }
+
+        let cache = &self.detail_cache;
+        self.details = self.list.iter().map(|name| cache.get(name).cloned().unwrap_or_default()).collect();
+    }
+
+    fn filter_snapshots(&mut self) {
+
+        let prefixes = &self.prefixes;
+        let class = |name: &str| retention::parse_name(name, prefixes).map(|auto| auto.class);
+
+        let mut entries = self.list.iter()
+            .cloned()
+            .zip(self.details.iter().cloned())
+            .filter(|(name, _)| match self.filter.as_str() {
+                ""       => true,
+                "manual" => class(name).is_none(),
+                filter   => class(name).as_deref() == Some(filter),
+            })
+            .collect::<Vec<(String, String)>>();
+
+        if self.is_grouped {
+            entries.sort_by_key(|(name, _)| class(name).unwrap_or_default());
+        }
+
+        self.list = entries.iter().map(|(name, _)| name.clone()).collect();
+        self.details = entries.into_iter().map(|(_, detail)| detail).collect();
+    }
+
+    pub fn toggle_mark(&mut self) {
+
+        if let Some(value) = self.list.get(self.position as usize) {
+            match self.marked.iter().position(|name| name == value) {
+                Some(i) => { self.marked.remove(i); },
+                None    => { self.marked.push(value.clone()); },
+            }
         }
+
+        self.jump(1);
     }
 
     pub fn next(&mut self) {
@@ -45,13 +139,20 @@ impl Content {
             ContentType::Pools     => { ContentType::Datasets  },
             ContentType::Datasets  => { ContentType::Volumes   },
             ContentType::Volumes   => { ContentType::Snapshots },
-            ContentType::Snapshots => { ContentType::Pools     },
+            ContentType::Snapshots => { ContentType::Bookmarks },
+            ContentType::Bookmarks => { ContentType::Pools     },
         };
 
         self.content_type = next_type;
         self.position = 0;
         self.start = 0;
         self.list = Vec::new();
+        self.details = Vec::new();
+        self.marked = Vec::new();
+        self.filter = String::new();
+        self.is_grouped = false;
+        self.detail_cache = HashMap::new();
+        self.detail_time = None;
     }
 
     pub fn title(&self) -> String {
@@ -59,7 +160,13 @@ impl Content {
             ContentType::Pools     => { String::from("Pools")     },
             ContentType::Datasets  => { String::from("Datasets")  },
             ContentType::Volumes   => { String::from("Volumes")   },
-            ContentType::Snapshots => { String::from("Snapshots") },
+            ContentType::Snapshots => {
+                let mut title = String::from("Snapshots");
+                if !self.filter.is_empty() { title.push_str(&format!(" [{}]", self.filter)); }
+                if self.is_grouped         { title.push_str(" [grouped]"); }
+                title
+            },
+            ContentType::Bookmarks => { String::from("Bookmarks") },
         }
     }
 
@@ -115,6 +222,7 @@ impl Content {
             Some(value) => value,
             _     => "",
         };
+        let marked = &self.marked;
     
         match function_key {
     
@@ -122,46 +230,51 @@ impl Content {
     
             2 => {
                 match self.content_type {
-                    ContentType::Pools =>     { },
+                    ContentType::Pools =>     { zpool::pool_status(selected_value);    },
                     ContentType::Datasets =>  { zfs::dataset_promote(selected_value);  },
                     ContentType::Volumes =>   { },
                     ContentType::Snapshots => { zfs::snapshot_diff(selected_value);    },
+                    ContentType::Bookmarks => { },
                 }
             },
     
             3 => { 
                 match self.content_type {
-                    ContentType::Pools =>     { },
-                    ContentType::Datasets =>  { },
+                    ContentType::Pools =>     { zpool::pool_iostat(selected_value);    },
+                    ContentType::Datasets =>  { zfs::dataset_receive(selected_value);  },
                     ContentType::Volumes =>   { },
                     ContentType::Snapshots => { zfs::snapshot_send(selected_value);    },    
+                    ContentType::Bookmarks => { zfs::bookmark_send(selected_value);    },
                 }
             },
     
             4 => { 
                 match self.content_type {
-                    ContentType::Pools =>     { },
+                    ContentType::Pools =>     { zpool::pool_history(selected_value);   },
                     ContentType::Datasets =>  { },
                     ContentType::Volumes =>   { },
-                    ContentType::Snapshots => { },
+                    ContentType::Snapshots => { zfs::snapshot_holds(selected_value, marked); },
+                    ContentType::Bookmarks => { },
                 }
             },
     
             5 => { 
                 match self.content_type {
-                    ContentType::Pools =>     { },
+                    ContentType::Pools =>     { zpool::pool_events();                  },
                     ContentType::Datasets =>  { zfs::dataset_snapshot(selected_value); },
                     ContentType::Volumes =>   { zfs::dataset_snapshot(selected_value); },
                     ContentType::Snapshots => { zfs::snapshot_clone(selected_value);   },
+                    ContentType::Bookmarks => { },
                 }
             },
     
             6 => { 
                 match self.content_type {
-                    ContentType::Pools =>     { },
+                    ContentType::Pools =>     { zpool::pool_features(selected_value);   },
                     ContentType::Datasets =>  { zfs::dataset_rename(selected_value);    },
                     ContentType::Volumes =>   { zfs::dataset_rename(selected_value);    },
                     ContentType::Snapshots => { zfs::dataset_rename(selected_value);    },
+                    ContentType::Bookmarks => { zfs::bookmark_rename(selected_value);  },
                 }
             },
     
@@ -171,6 +284,7 @@ impl Content {
                     ContentType::Datasets =>  { zfs::dataset_create(selected_value);    },
                     ContentType::Volumes =>   { zfs::volume_create(selected_value);     },
                     ContentType::Snapshots => { zfs::snapshot_rollback(selected_value); },
+                    ContentType::Bookmarks => { },
                 }
             },
     
@@ -179,7 +293,8 @@ impl Content {
                     ContentType::Pools =>     { zpool::pool_destroy(selected_value);    },
                     ContentType::Datasets =>  { zfs::dataset_destroy(selected_value);   },
                     ContentType::Volumes =>   { zfs::dataset_destroy(selected_value);   },
-                    ContentType::Snapshots => { zfs::dataset_destroy(selected_value);   },
+                    ContentType::Snapshots => { zfs::snapshot_destroy(selected_value, marked); },
+                    ContentType::Bookmarks => { zfs::bookmark_destroy(selected_value);  },
                 }
             },
     
@@ -189,22 +304,74 @@ impl Content {
                     ContentType::Datasets =>  { zfs::dataset_get_all(selected_value);   },
                     ContentType::Volumes =>   { zfs::dataset_get_all(selected_value);   },
                     ContentType::Snapshots => { zfs::dataset_get_all(selected_value);   },
+                    ContentType::Bookmarks => { zfs::dataset_get_all(selected_value);   },
                 }
             },
     
-            11 => { },
+            11 => {
+                match self.content_type {
+                    ContentType::Pools =>     { zpool::pool_checkpoint(selected_value); },
+                    ContentType::Datasets =>  { },
+                    ContentType::Volumes =>   { },
+                    ContentType::Snapshots => { zfs::snapshot_bookmark(selected_value); },
+                    ContentType::Bookmarks => { },
+                }
+            },
     
-            12 => { },
+            12 => {
+                match self.content_type {
+                    ContentType::Pools =>     { zpool::pool_more(selected_value);     },
+                    ContentType::Datasets =>  { },
+                    ContentType::Volumes =>   { },
+                    ContentType::Snapshots => { zfs::snapshot_more(selected_value, &mut self.filter, &mut self.is_grouped); },
+                    ContentType::Bookmarks => { },
+                }
+            },
     
             _ => { },
         }
+
+        self.detail_time = None;
     }
 }
 
+pub fn human_size(bytes: u64) -> String {
+
+    const UNITS: [&str; 6] = ["B", "K", "M", "G", "T", "P"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 { format!("{}{}", bytes, UNITS[0]) }
+    else         { format!("{:.1}{}", value, UNITS[unit]) }
+}
+
+pub fn pool_warnings() -> Vec<String> {
+
+    let config = Config::load();
+
+    zpool::pool_capacities()
+        .iter()
+        .flat_map(|capacity| capacity.warnings(&config))
+        .collect()
+}
+
 mod zpool {
 
+    use std::collections::{HashMap, VecDeque};
+
+    use std::sync::OnceLock;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
     use crate::commands;
+    use crate::config::Config;
     use crate::dialogs;
+    use super::human_size;
 
     pub fn pool_list() -> Vec<String> {
         commands::list("zpool", &["list", "-H", "-o", "name"])
@@ -248,214 +415,1718 @@ mod zpool {
     
             if !result.is_empty() {
                 dialogs::message_box(err_title, err_prompt, result);
+            } else {
+                pool_status(selected_value);
             }
         } 
     }
-    
-    pub fn pool_get_all(selected_value: &str) {
 
-        let title = "ZPOOL Get All";
-        let err_title = "Error";
-        let err_prompt = "Error during zpool set";
+    pub struct ScanStatus {
+        pub state:   String,
+        pub percent: Option<f32>,
+        pub eta:     Option<String>,
+        pub lines:   Vec<String>,
+    }
 
-        let arguments = vec!["get", "all", selected_value];
-        let result = commands::list("zpool", &arguments);    
+    impl ScanStatus {
 
-        let clone = result.clone().get(1..).unwrap().to_vec();
-        let legend = result.get(0).unwrap();
+        pub fn is_running(&self) -> bool {
+            self.state.ends_with("in progress")
+        }
+    }
 
-        if let Ok(selection) = dialogs::navigation_box(title, &legend, clone) {
+    pub fn pool_scan_status(selected_value: &str) -> ScanStatus {
 
-            let title = "ZPOOL Set";
-            let p_name: String;
-            let p_type: String;
-            let p_value: String;
-            let value: String;
+        let arguments = vec!["status", "-t", "-i", selected_value];
+        let result = commands::list("zpool", &arguments);
 
-            if !selection.is_empty() {
-                let mut p = selection.split_whitespace();
+        let mut scan: Vec<String> = Vec::new();
+        let mut activity: Vec<String> = Vec::new();
+        let mut is_scan = false;
 
-                p_name  = p.next().unwrap().to_string();
-                p_type  = p.next().unwrap().to_string();
-                p_value = p.next().unwrap().to_string();
+        for line in result.iter() {
+            let trimmed = line.trim();
 
-                let prompt = format!("{}:{}", p_name, p_type);
-                value = dialogs::single_input_box(title, &prompt, p_value);
+            if trimmed.starts_with("scan:") {
+                is_scan = true;
+                scan.push(trimmed.trim_start_matches("scan:").trim().to_string());
+
+            } else if is_scan && !trimmed.contains(": ") && !trimmed.ends_with(':') && !trimmed.is_empty() {
+                scan.push(trimmed.to_string());
 
             } else {
-                let prompt = String::from("Enter property and value:");
-                let results = dialogs::dual_input_box(title, &prompt, "property".to_string(), "value".to_string());
-                p_type = results.0;
-                value = results.1;
+                is_scan = false;
+
+                if trimmed.contains("% trimmed") || trimmed.contains("% initialized") {
+                    activity.push(trimmed.to_string());
+                }
             }
+        }
 
-            if !value.is_empty() {
-                
-                let property_value = format!("{}={}", p_type, value);
-                let arguments = vec!["set", &property_value, &selected_value];
-                let result = commands::list("zpool", &arguments);
-            
-                if !result.is_empty() {
-                    dialogs::message_box(err_title, err_prompt, result);
-                }    
+        let first = scan.first().cloned().unwrap_or_default();
+        let operation = first.split_whitespace().next().unwrap_or("").to_string();
+
+        let state = if first.contains("in progress") {
+            format!("{} in progress", operation)
+        } else if first.contains("paused") {
+            format!("{} paused", operation)
+        } else if first.contains("canceled") {
+            format!("{} canceled", operation)
+        } else if first.starts_with("scrub repaired") {
+            String::from("scrub finished")
+        } else if first.starts_with("resilvered") {
+            String::from("resilver finished")
+        } else if first.is_empty() {
+            String::from("none requested")
+        } else {
+            first.clone()
+        };
+
+        let mut percent = None;
+        let mut eta = None;
+
+        for part in scan.iter().flat_map(|l| l.split(", ")) {
+            if let Some(value) = part.strip_suffix("% done") {
+                let value = value.split_whitespace().last().unwrap_or("");
+                percent = value.parse::<f32>().ok();
+            }
+            if let Some(value) = part.strip_suffix(" to go") {
+                eta = Some(value.to_string());
             }
         }
-    }
-}
 
-mod zfs {
+        let mut lines = scan;
+        lines.extend(activity);
 
-    use crate::commands;
-    use crate::dialogs;
-    
-    pub fn volume_list() -> Vec<String> {
-        commands::list("zfs",   &["list", "-H", "-o", "name", "-t", "volume"])
+        ScanStatus { state, percent, eta, lines }
     }
 
-    pub fn volume_create(selected_value: &str) {
-    
-        let title = "Create Volume";
-        let prompt = "Enter the name and size of the new volume: ";
-        let default_value = format!("{}", selected_value);
-    
-        let err_title = "Error";
-        let err_prompt = "Error during zfs create";
-        let default_value2 = format!("1g");
-    
-        let (new_dataset1, size) = dialogs::dual_input_box(title, prompt, default_value, default_value2);
-    
-        if !new_dataset1.is_empty() {
-            let arguments = vec!["create", "-V", &size, &new_dataset1];
-            let result = commands::list("zfs", &arguments);    
-    
-            if !result.is_empty() {
-                dialogs::message_box(err_title, err_prompt, result);
+    pub fn pool_summary(selected_value: &str) -> String {
+
+        let status = pool_scan_status(selected_value);
+        let mut summary = Vec::new();
+
+        if status.is_running() || status.state.ends_with("paused") {
+            summary.push(status.state.replace(" in progress", ""));
+
+            if let Some(percent) = status.percent {
+                summary.push(format!("{:.1}%", percent));
+            }
+            if let Some(eta) = status.eta {
+                summary.push(eta);
             }
         }
+
+        for line in status.lines.iter() {
+            if line.contains("% trimmed") && !line.contains("completed") {
+                summary.push(String::from("trim"));
+            } else if line.contains("% initialized") && !line.contains("completed") {
+                summary.push(String::from("init"));
+            } else {
+                continue;
+            }
+            break;
+        }
+
+        if let Some(checkpoint) = pool_checkpoint_size(selected_value) {
+            summary.push(format!("ckpt {}", human_size(checkpoint)));
+        }
+
+        summary.join(" ")
     }
-    
-    pub fn dataset_list() -> Vec<String> {
-        commands::list("zfs",   &["list", "-H", "-o", "name", "-t", "filesystem"])
+
+    pub fn pool_checkpoint_size(selected_value: &str) -> Option<u64> {
+
+        let arguments = vec!["get", "-H", "-p", "-o", "value", "checkpoint", selected_value];
+        let result = commands::run("zpool", &arguments);
+
+        result.trim().parse::<u64>().ok()
     }
 
-    pub fn dataset_destroy(selected_value: &str) {
-    
-        let title = "Destroy Dataset";
-        let prompt = "The following dataset will be destroyed: ";
-        let message = vec![String::from(selected_value)];
-    
-        let err_title = "Error";
-        let err_prompt = "Error during zfs destroy";
-    
-        let is_confirmed = dialogs::confirmation_box(title, prompt, message);
-    
-        if is_confirmed {
-            let arguments = vec!["destroy", selected_value];
-            let result = commands::list("zfs", &arguments);    
-    
-            if !result.is_empty() {
-                dialogs::message_box(err_title, err_prompt, result);
-            }
-        }    
+    pub fn pool_more(selected_value: &str) {
+
+        let title = "Pool Actions";
+        let prompt = "Select the action:";
+        let items = vec![
+            String::from("Recovery import assistant (zpool import -F/-X/-T)"),
+            String::from("Split mirrors into a new pool (zpool split)"),
+            String::from("Generate a new pool GUID (zpool reguid)"),
+            String::from("Rename pool (export and import with a new name)"),
+            String::from("Capacity dashboard"),
+        ];
+
+        match dialogs::menu_box(title, prompt, items) {
+            Some(0) => { pool_recovery(); },
+            Some(1) => { pool_split(selected_value); },
+            Some(2) => { pool_reguid(selected_value); },
+            Some(3) => { pool_rename(selected_value); },
+            Some(4) => { pool_capacity_dashboard(); },
+            _ => { },
+        }
     }
-    
-    pub fn dataset_create(selected_value: &str) {
-    
-        let title = "Create Dataset";
-        let prompt = "Enter the name of the new dataset: ";
-        let default_value = format!("{}/", selected_value);
-    
-        let err_title = "Error";
-        let err_prompt = "Error during zfs create";
-    
-        let new_dataset = dialogs::single_input_box(title, prompt, default_value);
-    
-        if !new_dataset.is_empty() {
-            let arguments = vec!["create", &new_dataset];
-            let result = commands::list("zfs", &arguments);    
-    
-            if !result.is_empty() {
-                dialogs::message_box(err_title, err_prompt, result);
-            }
-        }    
+
+    pub struct PoolCapacity {
+        pub name:          String,
+        pub health:        String,
+        pub size:          u64,
+        pub allocated:     u64,
+        pub free:          u64,
+        pub fragmentation: Option<u64>,
+        pub capacity:      u64,
+        pub dedup:         String,
+        pub scrub_age:     Option<u64>,
     }
-    
-    pub fn dataset_snapshot(selected_value: &str) {
-    
-        let title = "Snapshot Dataset";
-        let prompt = "Enter the name of the new snapshot: ";
-        let default_value = format!("{}@", selected_value);
-    
-        let err_title = "Error";
-        let err_prompt = "Error during zfs snapshot";
-    
-        let new_dataset = dialogs::single_input_box(title, prompt, default_value);
-    
-        if !new_dataset.is_empty() {
-            let arguments = vec!["snapshot", &new_dataset];
-            let result = commands::list("zfs", &arguments);    
-    
-            if !result.is_empty() {
-                dialogs::message_box(err_title, err_prompt, result);
+
+    impl PoolCapacity {
+
+        pub fn warnings(&self, config: &Config) -> Vec<String> {
+
+            let mut warnings = Vec::new();
+
+            if self.health != "ONLINE" {
+                warnings.push(format!("{}: health {}", self.name, self.health));
             }
-        }   
-    }
-    
-    pub fn dataset_rename(selected_value: &str) {
-    
-        let title = "Rename Dataset";
-        let prompt = "Enter the new name for the dataset: ";
-        let default_value = format!("{}", selected_value);
-    
-        let err_title = "Error";
-        let err_prompt = "Error during zfs rename";
-    
-        let new_dataset = dialogs::single_input_box(title, prompt, default_value);
-    
-        if !new_dataset.is_empty() {
-            let arguments = vec!["rename", selected_value, &new_dataset];
-            let result = commands::list("zfs", &arguments);    
-    
-            if !result.is_empty() {
-                dialogs::message_box(err_title, err_prompt, result);
+            if self.capacity > config.capacity_warning {
+                warnings.push(format!("{}: capacity {}% (>{}%)", self.name, self.capacity, config.capacity_warning));
             }
-        }   
-    }
-    
-    pub fn dataset_promote(selected_value: &str) {
-    
-        let title = "Promote Dataset";
-        let prompt = "Promote the following dataset: ";
-        let default_value = vec![String::from(selected_value)];
-    
-        let err_title = "Error";
-        let err_prompt = "Error during zfs promote";
-    
-        let is_confirmed = dialogs::confirmation_box(title, prompt, default_value);
-    
-        if is_confirmed {
-            let arguments = vec!["promote", selected_value];
-            let result = commands::list("zfs", &arguments);    
-    
-            if !result.is_empty() {
-                dialogs::message_box(err_title, err_prompt, result);
+            if let Some(fragmentation) = self.fragmentation.filter(|f| *f > config.fragmentation_warning) {
+                warnings.push(format!("{}: fragmentation {}% (>{}%)", self.name, fragmentation, config.fragmentation_warning));
             }
-        }     
+            match self.scrub_age {
+                Some(days) if days > config.scrub_age_warning => {
+                    warnings.push(format!("{}: last scrub {} days ago (>{} days)", self.name, days, config.scrub_age_warning));
+                },
+                None => { warnings.push(format!("{}: never scrubbed", self.name)); },
+                _ => { },
+            }
+
+            warnings
+        }
     }
-    
-    pub fn dataset_get_all(selected_value: &str) {
 
-        let title = "ZFS Get All";
-        let err_title = "Error";
-        let err_prompt = "Error during zfs set";
+    fn scrub_age(selected_value: &str) -> Option<u64> {
 
-        let arguments = vec!["get", "all", selected_value];
-        let result = commands::list("zfs", &arguments);    
+        let status = pool_scan_status(selected_value);
 
-        let clone = result.clone().get(1..).unwrap().to_vec();
-        let legend = result.get(0).unwrap();
+        if status.state == "scrub in progress" {
+            return Some(0);
+        }
 
-        if let Ok(selection) = dialogs::navigation_box(title, &legend, clone) {
+        let line = status.lines.first().filter(|line| line.starts_with("scrub repaired"))?;
+        let (_, date) = line.rsplit_once(" on ")?;
+        let scrubbed = local_timestamp(date)?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+
+        Some((now - scrubbed).max(0) as u64 / 86400)
+    }
+
+    fn local_timestamp(date: &str) -> Option<i64> {
+
+        const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+        let fields = date.split_whitespace().collect::<Vec<&str>>();
+        if fields.len() != 5 {
+            return None;
+        }
+
+        let month = MONTHS.iter().position(|month| *month == fields[1])? as i64 + 1;
+        let day = fields[2].parse::<i64>().ok()?;
+        let year = fields[4].parse::<i64>().ok()?;
+        let time = fields[3].split(':').map(|n| n.parse::<i64>().ok()).collect::<Option<Vec<i64>>>()?;
+        if time.len() != 3 {
+            return None;
+        }
+
+        let (y, m) = if month <= 2 { (year - 1, month + 9) } else { (year, month - 3) };
+        let era = y.div_euclid(400);
+        let year_of_era = y - era * 400;
+        let day_of_year = (153 * m + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146097 + day_of_era - 719468;
+
+        Some(days * 86400 + time[0] * 3600 + time[1] * 60 + time[2] - utc_offset())
+    }
+
+    fn utc_offset() -> i64 {
+
+        static OFFSET: OnceLock<i64> = OnceLock::new();
+
+        *OFFSET.get_or_init(|| {
+            let offset = commands::run("date", &["+%z"]);
+            let offset = offset.trim();
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let digits = offset.trim_start_matches(['+', '-']);
+            match (digits.get(0..2).and_then(|h| h.parse::<i64>().ok()), digits.get(2..4).and_then(|m| m.parse::<i64>().ok())) {
+                (Some(hours), Some(minutes)) => sign * (hours * 3600 + minutes * 60),
+                _                            => 0,
+            }
+        })
+    }
+
+    pub fn pool_capacities() -> Vec<PoolCapacity> {
+
+        let arguments = vec!["list", "-H", "-p", "-o", "name,size,allocated,free,fragmentation,capacity,dedupratio,health"];
+        let result = commands::list("zpool", &arguments);
+
+        result.iter()
+            .map(|line| line.split('\t').collect::<Vec<&str>>())
+            .filter(|fields| fields.len() >= 8)
+            .map(|fields| {
+                let number = |i: usize| fields[i].trim_end_matches('%').parse::<u64>().ok();
+
+                PoolCapacity {
+                    name:          fields[0].to_string(),
+                    size:          number(1).unwrap_or(0),
+                    allocated:     number(2).unwrap_or(0),
+                    free:          number(3).unwrap_or(0),
+                    fragmentation: number(4),
+                    capacity:      number(5).unwrap_or(0),
+                    dedup:         fields[6].trim_end_matches('x').to_string(),
+                    health:        fields[7].to_string(),
+                    scrub_age:     scrub_age(fields[0]),
+                }
+            })
+            .collect()
+    }
+
+    pub fn pool_capacity_dashboard() {
+
+        let title = "Pool Capacity";
+        let footnote = "F10 Close";
+        let (_, max_x) = dialogs::screen_dimensions();
+        let bar_width = (max_x as usize).saturating_sub(40);
+
+        let mut update = || {
+
+            let config = Config::load();
+            let mut message = vec![format!("Thresholds: capacity >{}%  fragmentation >{}%  last scrub >{} days  (set in {})",
+                config.capacity_warning, config.fragmentation_warning, config.scrub_age_warning, crate::config::CONFIG_FILE)];
+
+            for pool in pool_capacities().iter() {
+                message.push(String::new());
+                message.push(format!("{}  {}  size {}  allocated {}  free {}  dedup {}x",
+                    pool.name, pool.health, human_size(pool.size), human_size(pool.allocated),
+                    human_size(pool.free), pool.dedup));
+
+                message.push(format!("  capacity       {}", dialogs::progress_bar(pool.capacity as f32, bar_width)));
+                match pool.fragmentation {
+                    Some(f) => message.push(format!("  fragmentation  {}", dialogs::progress_bar(f as f32, bar_width))),
+                    None    => message.push(String::from("  fragmentation  -")),
+                }
+                match pool.scrub_age {
+                    Some(days) => message.push(format!("  last scrub     {} days ago", days)),
+                    None       => message.push(String::from("  last scrub     never")),
+                }
+
+                message.extend(pool.warnings(&config).iter().map(|w| format!("  ! {}", w)));
+            }
+
+            message
+        };
+
+        dialogs::live_box(title, footnote, &mut update);
+    }
+
+    fn pool_topology(selected_value: &str) -> Vec<(usize, String)> {
+
+        let result = commands::list("zpool", &["status", selected_value]);
+        let mut topology = Vec::new();
+        let mut is_config = false;
+
+        for line in result.iter() {
+            let trimmed = line.trim_start_matches('\t');
+
+            if trimmed.trim_start().starts_with("NAME ") {
+                is_config = true;
+                continue;
+            }
+            if is_config && (trimmed.trim().is_empty() || trimmed.starts_with("errors:")) {
+                break;
+            }
+            if is_config {
+                let indent = (trimmed.len() - trimmed.trim_start().len()) / 2;
+                let name = trimmed.split_whitespace().next().unwrap_or("").to_string();
+                topology.push((indent, name));
+            }
+        }
+
+        topology
+    }
+
+    fn topology_lines(topology: &[(usize, String)], root: &str) -> Vec<String> {
+
+        topology.iter()
+            .map(|(indent, name)| {
+                let name = if *indent == 0 { root } else { name.as_str() };
+                format!("{}{}", "  ".repeat(*indent), name)
+            })
+            .collect()
+    }
+
+    fn pool_guid(selected_value: &str) -> String {
+        commands::run("zpool", &["get", "-H", "-o", "value", "guid", selected_value]).trim().to_string()
+    }
+
+    pub fn pool_split(selected_value: &str) {
+
+        let title = "Split Pool";
+        let err_title = "Error";
+
+        let topology = pool_topology(selected_value);
+        let top_level = topology.iter()
+            .enumerate()
+            .skip(1)
+            .take_while(|(_, (indent, _))| *indent > 0)
+            .filter(|(_, (indent, _))| *indent == 1)
+            .collect::<Vec<(usize, &(usize, String))>>();
+
+        if top_level.iter().any(|(_, (_, name))| !name.starts_with("mirror")) {
+            let message = vec![String::from("zpool split requires every data vdev of the pool to be a mirror.")];
+            dialogs::message_box(err_title, "Unable to split the pool", message);
+            return;
+        }
+
+        let new_pool = dialogs::single_input_box(title, "Enter the name of the new pool: ", String::new());
+        if new_pool.is_empty() { return; }
+
+        let mut devices: Vec<String> = Vec::new();
+
+        for (i, (_, (_, mirror))) in top_level.iter().enumerate() {
+            let children = topology.iter()
+                .skip(top_level[i].0 + 1)
+                .take_while(|(indent, _)| *indent > 1)
+                .filter(|(indent, _)| *indent == 2)
+                .map(|(_, name)| name.clone())
+                .collect::<Vec<String>>();
+
+            let mut items = children.clone();
+            items.rotate_right(1);
+
+            let prompt = format!("Device of {} that goes to pool {} (first is the zpool default):", mirror, new_pool);
+            match dialogs::menu_box(title, &prompt, items.clone()) {
+                Some(index) => { devices.push(items[index].clone()); },
+                None        => { return; },
+            }
+        }
+
+        let mut arguments = vec!["split", "-n", selected_value, &new_pool];
+        arguments.extend(devices.iter().map(|d| d.as_str()));
+
+        let mut preview = vec![format!("$ zpool {}", arguments.join(" ")), String::new()];
+        preview.extend(commands::list("zpool", &arguments));
+        preview.push(String::new());
+        preview.push(format!("Remaining topology of {}:", selected_value));
+        preview.extend(topology_lines(&topology, selected_value).into_iter()
+            .filter(|line| !devices.contains(&line.trim().to_string())));
+
+        let prompt = "Resulting topology of the new pool (dry run):";
+        dialogs::presentation_box(title, prompt, preview);
+
+        let prompt = format!("Split {} into the new pool {} using:", selected_value, new_pool);
+        if dialogs::confirmation_box(title, &prompt, devices.clone()) {
+            arguments.remove(1);
+            let result = commands::list("zpool", &arguments);
+
+            if !result.is_empty() {
+                dialogs::message_box(err_title, "Error during zpool split", result);
+            } else {
+                let message = vec![format!("The new pool is exported; import it with: zpool import {}", new_pool)];
+                dialogs::message_box(title, "Pool split", message);
+            }
+        }
+    }
+
+    pub fn pool_reguid(selected_value: &str) {
+
+        let title = "Reguid Pool";
+        let guid = pool_guid(selected_value);
+
+        let mut preview = vec![
+            format!("Current GUID: {}", guid),
+            String::from("A new random GUID will be generated; the topology is unchanged:"),
+            String::new(),
+        ];
+        preview.extend(topology_lines(&pool_topology(selected_value), selected_value));
+
+        dialogs::presentation_box(title, "Preview:", preview);
+
+        let prompt = "A new GUID will be generated for the following pool: ";
+        let message = vec![String::from(selected_value)];
+
+        if dialogs::confirmation_box(title, prompt, message) {
+            let result = commands::list("zpool", &["reguid", selected_value]);
+
+            if !result.is_empty() {
+                dialogs::message_box("Error", "Error during zpool reguid", result);
+            } else {
+                let message = vec![format!("{} -> {}", guid, pool_guid(selected_value))];
+                dialogs::message_box(title, "Pool GUID changed:", message);
+            }
+        }
+    }
+
+    pub fn pool_rename(selected_value: &str) {
+
+        let title = "Rename Pool";
+        let err_title = "Error";
+
+        let prompt = "Enter the new name for the pool: ";
+        let new_name = dialogs::single_input_box(title, prompt, selected_value.to_string());
+        if new_name.is_empty() || new_name == selected_value { return; }
+
+        let guid = pool_guid(selected_value);
+        let mut preview = vec![format!("$ zpool export {}", selected_value), format!("$ zpool import {} {}", guid, new_name), String::new()];
+        preview.extend(topology_lines(&pool_topology(selected_value), &new_name));
+
+        dialogs::presentation_box(title, "Resulting topology:", preview);
+
+        let prompt = format!("Pool {} will be exported and imported as:", selected_value);
+        let message = vec![new_name.clone()];
+
+        if !dialogs::confirmation_box(title, &prompt, message) {
+            return;
+        }
+
+        let result = commands::list("zpool", &["export", selected_value]);
+        if !result.is_empty() {
+            dialogs::message_box(err_title, "Error during zpool export, the pool was not renamed", result);
+            return;
+        }
+
+        let result = commands::list("zpool", &["import", &guid, &new_name]);
+        if !result.is_empty() {
+            let mut message = result;
+            message.push(format!("The pool is exported; import it with: zpool import {} {}", guid, new_name));
+            dialogs::message_box(err_title, "Error during zpool import", message);
+        }
+    }
+
+    pub fn pool_checkpoint(selected_value: &str) {
+
+        let title = "Pool Checkpoint";
+        let items = vec![
+            String::from("Create checkpoint (zpool checkpoint)"),
+            String::from("Discard checkpoint (zpool checkpoint -d)"),
+            String::from("Rewind pool to checkpoint (export and import --rewind-to-checkpoint)"),
+        ];
+
+        let prompt = match pool_checkpoint_size(selected_value) {
+            Some(size) => format!("Pool {} has a checkpoint consuming {}", selected_value, human_size(size)),
+            None       => format!("Pool {} has no checkpoint", selected_value),
+        };
+
+        match dialogs::menu_box(title, &prompt, items) {
+            Some(0) => {
+                let prompt = "A checkpoint will be taken of the following pool: ";
+                let message = vec![String::from(selected_value)];
+
+                if dialogs::confirmation_box("Create Checkpoint", prompt, message) {
+                    pool_command(selected_value, &["checkpoint"], "Error during zpool checkpoint");
+                }
+            },
+            Some(1) => {
+                let prompt = "The checkpoint of the following pool will be discarded: ";
+                let message = vec![String::from(selected_value)];
+
+                if dialogs::confirmation_box("Discard Checkpoint", prompt, message) {
+                    pool_command(selected_value, &["checkpoint", "-d"], "Error during zpool checkpoint -d");
+                }
+            },
+            Some(2) => { pool_rewind_to_checkpoint(selected_value); },
+            _ => { },
+        }
+    }
+
+    fn pool_rewind_to_checkpoint(selected_value: &str) {
+
+        let title = "Rewind to Checkpoint";
+        let err_title = "Error";
+
+        if pool_checkpoint_size(selected_value).is_none() {
+            let message = vec![format!("Pool {} has no checkpoint to rewind to.", selected_value)];
+            dialogs::message_box(err_title, "Unable to rewind", message);
+            return;
+        }
+
+        let prompt = "The pool will be exported and imported at its checkpoint:";
+        let message = vec![
+            String::from("ALL changes made after the checkpoint was taken will be LOST,"),
+            String::from("including new datasets, snapshots and written data. The pool"),
+            String::from("will be unavailable while it is exported and imported again."),
+        ];
+
+        if !dialogs::typed_confirmation_box(title, prompt, message, selected_value) {
+            return;
+        }
+
+        let result = commands::list("zpool", &["export", selected_value]);
+        if !result.is_empty() {
+            dialogs::message_box(err_title, "Error during zpool export, the pool was not rewound", result);
+            return;
+        }
+
+        let result = commands::list("zpool", &["import", "--rewind-to-checkpoint", selected_value]);
+        if !result.is_empty() {
+            let mut message = result;
+            message.push(format!("The pool is exported; import it with: zpool import {}", selected_value));
+            dialogs::message_box(err_title, "Error during zpool import --rewind-to-checkpoint", message);
+        }
+    }
+
+    pub fn pool_status(selected_value: &str) {
+
+        let title = format!("Pool Status: {}", selected_value);
+        let footnote = "F2 Scrub  F3 Pause  F4 Stop  F5 Trim  F6 Stop trim  F7 Initialize  F10 Close";
+
+        let mut update = || {
+            let status = pool_scan_status(selected_value);
+
+            let mut message = vec![format!("State: {}", status.state)];
+            if let Some(eta) = status.eta.as_ref() {
+                message.push(format!("Estimated time to completion: {}", eta));
+            }
+            message.push(String::new());
+            message.extend(status.lines.iter().cloned());
+
+            let percent = if status.is_running() || status.state.ends_with("paused") {
+                status.percent
+            } else {
+                None
+            };
+
+            (message, percent)
+        };
+
+        while let Some(function_key) = dialogs::progress_box(&title, footnote, &mut update) {
+
+            match function_key {
+                2 => { pool_command(selected_value, &["scrub"], "Error during zpool scrub"); },
+                3 => { pool_command(selected_value, &["scrub", "-p"], "Error during zpool scrub -p"); },
+                4 => { pool_command(selected_value, &["scrub", "-s"], "Error during zpool scrub -s"); },
+                5 => { pool_command(selected_value, &["trim"], "Error during zpool trim"); },
+                6 => { pool_command(selected_value, &["trim", "-s"], "Error during zpool trim -s"); },
+                7 => {
+                    let prompt = "All unallocated space of the following pool will be initialized: ";
+                    let message = vec![String::from(selected_value)];
+
+                    if dialogs::confirmation_box("Initialize Pool", prompt, message) {
+                        pool_command(selected_value, &["initialize"], "Error during zpool initialize");
+                    }
+                },
+                _ => { },
+            }
+        }
+    }
+
+    const SPARK_LENGTH: usize = 24;
+    const SPARK_LEVELS: &[u8] = b" .:-=+*#%@";
+
+    struct IostatRow {
+        pool:    String,
+        name:    String,
+        is_pool: bool,
+        values:  Vec<u64>,
+    }
+
+    fn iostat_rows(lines: &[String], pools: &[String]) -> Vec<IostatRow> {
+
+        let mut rows = Vec::new();
+        let mut pool = String::new();
+
+        for line in lines.iter() {
+            let fields: Vec<&str> = line.split('\t').map(|f| f.trim()).collect();
+            if fields.len() < 7 { continue; }
+
+            let name = fields[0].to_string();
+            let is_pool = pools.contains(&name);
+            if is_pool { pool = name.clone(); }
+
+            let values = fields[3..7].iter()
+                .map(|v| v.parse::<u64>().unwrap_or(0))
+                .collect::<Vec<u64>>();
+
+            rows.push(IostatRow { pool: pool.clone(), name, is_pool, values });
+        }
+
+        rows
+    }
+
+    fn sparkline(samples: &VecDeque<u64>) -> String {
+
+        let max = samples.iter().cloned().max().unwrap_or(0);
+
+        samples.iter()
+            .map(|sample| {
+                if max == 0 { return ' '; }
+                let level = (*sample as f64 / max as f64 * (SPARK_LEVELS.len() - 1) as f64).round();
+                SPARK_LEVELS[level as usize] as char
+            })
+            .collect::<String>()
+    }
+
+    fn latency_histogram(selected_value: &str, width: usize) -> Vec<String> {
+
+        let arguments = vec!["iostat", "-w", "-H", "-p", selected_value];
+        let result = commands::list("zpool", &arguments);
+
+        let rows = result.iter()
+            .map(|line| line.split('\t').map(|f| f.trim().to_string()).collect::<Vec<String>>())
+            .filter(|fields| fields.len() >= 3 && fields[0].parse::<u64>().is_ok())
+            .collect::<Vec<Vec<String>>>();
+
+        if rows.is_empty() {
+            return result;
+        }
+
+        let value = |fields: &Vec<String>, i: usize| fields[i].parse::<u64>().unwrap_or(0);
+        let max = rows.iter().map(|f| value(f, 1).max(value(f, 2))).max().unwrap_or(0).max(1);
+        let bar_width = width.saturating_sub(40) / 2;
+
+        let mut histogram = vec![
+            format!("Cumulative total wait latency histogram for {}, counted since import (not live)", selected_value),
+            String::new(),
+            format!("{:>8}  {:<w$}  {:<w$}", "LATENCY", "READ", "WRITE", w = bar_width + 10),
+        ];
+
+        for fields in rows.iter() {
+            let bar = |count: u64| {
+                let length = (count as f64 / max as f64 * bar_width as f64).ceil() as usize;
+                format!("{:<w$} {:>9}", "#".repeat(length), count, w = bar_width)
+            };
+
+            histogram.push(format!("{:>8}  {}  {}", latency(value(fields, 0)), bar(value(fields, 1)), bar(value(fields, 2))));
+        }
+
+        histogram
+    }
+
+    fn latency(nanoseconds: u64) -> String {
+
+        match nanoseconds {
+            0..=999                         => format!("{}ns", nanoseconds),
+            1_000..=999_999                 => format!("{}us", nanoseconds / 1_000),
+            1_000_000..=999_999_999         => format!("{}ms", nanoseconds / 1_000_000),
+            _                               => format!("{}s",  nanoseconds / 1_000_000_000),
+        }
+    }
+
+    fn flush_iostat(pending: &mut Vec<String>, pools: &[String], reports: &mut usize,
+                    sample: &mut Vec<IostatRow>, history: &mut HashMap<String, (VecDeque<u64>, VecDeque<u64>)>) {
+
+        let rows = iostat_rows(pending, pools);
+        pending.clear();
+        *reports += 1;
+
+        // The first report holds the averages since boot, not a sample of the interval
+        if *reports == 1 { return; }
+
+        for row in rows.iter() {
+            let key = format!("{}/{}", row.pool, row.name);
+            let (reads, writes) = history.entry(key).or_insert((VecDeque::new(), VecDeque::new()));
+
+            reads.push_back(row.values[2]);
+            writes.push_back(row.values[3]);
+            if reads.len()  > SPARK_LENGTH { reads.pop_front(); }
+            if writes.len() > SPARK_LENGTH { writes.pop_front(); }
+        }
+
+        *sample = rows;
+    }
+
+    pub fn pool_iostat(selected_value: &str) {
+
+        let title = "ZPOOL Iostat";
+        let footnote = "F2 Cumulative histogram  F3 Interval  F10 Close";
+
+        let pools = pool_list();
+        let mut interval = String::from("1");
+        let mut show_histogram = false;
+
+        let report_length = commands::list("zpool", &["iostat", "-v", "-H", "-p"])
+            .iter()
+            .filter(|line| line.contains('\t'))
+            .count();
+
+        loop {
+            let arguments = vec!["iostat", "-v", "-H", "-p", &interval];
+            let follower = commands::follow("zpool", &arguments);
+
+            let mut pending: Vec<String> = Vec::new();
+            let mut sample: Vec<IostatRow> = Vec::new();
+            let mut errors: Vec<String> = Vec::new();
+            let mut history: HashMap<String, (VecDeque<u64>, VecDeque<u64>)> = HashMap::new();
+            let mut reports: usize = 0;
+
+            let mut update = || {
+
+                for line in follower.take() {
+                    let first = line.split('\t').next().unwrap_or("").trim().to_string();
+
+                    if !line.contains('\t') {
+                        errors.push(line);
+                        continue;
+                    }
+
+                    let is_next_report = pools.first() == Some(&first) && !pending.is_empty();
+                    if is_next_report {
+                        flush_iostat(&mut pending, &pools, &mut reports, &mut sample, &mut history);
+                    }
+
+                    pending.push(line);
+
+                    if pending.len() == report_length {
+                        flush_iostat(&mut pending, &pools, &mut reports, &mut sample, &mut history);
+                    }
+                }
+
+                if show_histogram {
+                    let (_, max_x) = dialogs::screen_dimensions();
+                    return latency_histogram(selected_value, (max_x as usize).saturating_sub(14));
+                }
+
+                let mut message = vec![format!("{:<20} {:>9} {:>9} {:>9} {:>9}  {:<w$}  {:<w$}",
+                    "NAME", "READ OPS", "WRITE OPS", "READ BW", "WRITE BW", "READ", "WRITE", w = SPARK_LENGTH)];
+
+                for row in sample.iter() {
+                    let key = format!("{}/{}", row.pool, row.name);
+                    let name = if row.is_pool { row.name.clone() } else { format!("  {}", row.name) };
+                    let (reads, writes) = history.get(&key).cloned().unwrap_or_default();
+
+                    message.push(format!("{:<20} {:>9} {:>9} {:>9} {:>9}  {:<w$}  {:<w$}",
+                        name, row.values[0], row.values[1],
+                        human_size(row.values[2]), human_size(row.values[3]),
+                        sparkline(&reads), sparkline(&writes), w = SPARK_LENGTH));
+                }
+
+                if sample.is_empty() {
+                    message.push(String::from("Waiting for the first sample..."));
+                }
+                message.extend(errors.iter().cloned());
+
+                message
+            };
+
+            match dialogs::live_box(title, footnote, &mut update) {
+                Some(2) => { show_histogram = !show_histogram; },
+                Some(3) => {
+                    let prompt = "Enter the sampling interval in seconds: ";
+                    let value = dialogs::single_input_box("Iostat Interval", prompt, interval.clone());
+                    if value.parse::<u32>().map(|v| v > 0).unwrap_or(false) {
+                        interval = value;
+                    }
+                },
+                Some(_) => { },
+                None    => { break; },
+            }
+        }
+    }
+
+    const HISTORY_TYPES: [&str; 7] = ["all", "create", "destroy", "snapshot", "set", "rename", "clone"];
+
+    fn history_command(line: &str) -> String {
+
+        let tokens: Vec<&str> = line.split_whitespace().skip(1).collect();
+
+        let command = match tokens.first() {
+            Some(token) if token.starts_with("[txg:") => tokens.get(1),
+            Some(&"zfs") | Some(&"zpool") | Some(&"ioctl") => tokens.get(1),
+            first => first,
+        };
+
+        command.map(|c| c.to_string()).unwrap_or_default()
+    }
+
+    pub fn pool_history(selected_value: &str) {
+
+        let title = format!("ZPOOL History: {}", selected_value);
+        let footnote = "F2 Dataset  F3 Type  F4 Dates  F5 Clear  F7 Find  F10 Close";
+
+        let arguments = vec!["history", "-il", selected_value];
+        let result = commands::list("zpool", &arguments);
+
+        let mut dataset = String::new();
+        let mut command_type = String::from(HISTORY_TYPES[0]);
+        let mut date_from = String::new();
+        let mut date_to = String::new();
+
+        loop {
+
+            let filtered = result.iter()
+                .filter(|line| line.len() >= 10 && line.as_bytes()[4] == b'-')
+                .filter(|line| dataset.is_empty() || line.contains(&dataset))
+                .filter(|line| command_type == HISTORY_TYPES[0] || history_command(line).contains(&command_type))
+                .filter(|line| date_from.is_empty() || line.get(..10).unwrap_or("") >= date_from.as_str())
+                .filter(|line| date_to.is_empty() || line.get(..10).unwrap_or("") <= date_to.as_str())
+                .cloned()
+                .collect::<Vec<String>>();
+
+            let prompt = format!("Dataset: {}  Type: {}  Dates: {} to {}  ({} of {} entries)",
+                if dataset.is_empty() { "all" } else { &dataset }, command_type,
+                if date_from.is_empty() { "start" } else { &date_from },
+                if date_to.is_empty() { "end" } else { &date_to },
+                filtered.len(), result.len().saturating_sub(1));
+
+            match dialogs::presentation_box_with_keys(&title, &prompt, footnote, filtered, &[2, 3, 4, 5]) {
+                Some(2) => {
+                    let prompt = "Show only entries mentioning the dataset: ";
+                    dataset = dialogs::single_input_box("Filter Dataset", prompt, dataset.clone());
+                },
+                Some(3) => {
+                    let items = HISTORY_TYPES.iter().map(|t| t.to_string()).collect::<Vec<String>>();
+                    if let Some(i) = dialogs::menu_box("Filter Type", "Show only the commands of type: ", items) {
+                        command_type = HISTORY_TYPES[i].to_string();
+                    }
+                },
+                Some(4) => {
+                    let prompt = "Enter the first and last dates (YYYY-MM-DD), empty for no limit:";
+                    let (from, to) = dialogs::dual_input_box("Filter Dates", prompt, date_from.clone(), date_to.clone());
+                    date_from = from;
+                    date_to = to;
+                },
+                Some(5) => {
+                    dataset.clear();
+                    command_type = String::from(HISTORY_TYPES[0]);
+                    date_from.clear();
+                    date_to.clear();
+                },
+                _ => { break; },
+            }
+        }
+    }
+
+    struct Event {
+        time:    String,
+        class:   String,
+        details: Vec<String>,
+    }
+
+    impl Event {
+
+        fn detail(&self, key: &str) -> String {
+            self.details.iter()
+                .filter_map(|line| line.split_once(" = "))
+                .find(|(k, _)| k.trim() == key)
+                .map(|(_, v)| v.trim().trim_matches('"').to_string())
+                .unwrap_or_default()
+        }
+
+        fn summary(&self) -> String {
+            let class = self.class
+                .trim_start_matches("ereport.fs.zfs.")
+                .trim_start_matches("sysevent.fs.zfs.")
+                .trim_start_matches("resource.fs.zfs.");
+
+            format!("{:<32} {:<20} {:<12} {}", self.time, class, self.detail("pool"), self.detail("vdev_path"))
+        }
+    }
+
+    fn parse_events(lines: &[String]) -> Vec<Event> {
+
+        let mut events: Vec<Event> = Vec::new();
+
+        for line in lines.iter() {
+            if line.trim().is_empty() || line.starts_with("TIME ") {
+                continue;
+
+            } else if line.starts_with(char::is_whitespace) {
+                if let Some(event) = events.last_mut() {
+                    event.details.push(line.trim().to_string());
+                }
+
+            } else {
+                let (time, class) = match line.rfind(' ') {
+                    Some(i) => (line[..i].trim().to_string(), line[i + 1..].to_string()),
+                    None    => (String::new(), line.to_string()),
+                };
+                events.push(Event { time, class, details: Vec::new() });
+            }
+        }
+
+        events
+    }
+
+    pub fn pool_events() {
+
+        let title = "ZPOOL Events";
+        let mut position = 0;
+        let mut is_expanded = false;
+        let mut follower: Option<commands::Follower> = None;
+        let mut lines = commands::list("zpool", &["events", "-v"]);
+
+        loop {
+
+            let footnote = match follower {
+                Some(_) => "ENTER Details  F2 Stop following  F3 Clear  F10 Close",
+                None    => "ENTER Details  F2 Follow  F3 Clear  F10 Close",
+            };
+
+            let mut update = || {
+                if let Some(follower) = follower.as_ref() {
+                    lines.extend(follower.take());
+                }
+
+                parse_events(&lines)
+                    .iter()
+                    .map(|event| (event.summary(), event.details.clone()))
+                    .collect::<Vec<(String, Vec<String>)>>()
+            };
+
+            match dialogs::detail_box(title, footnote, &mut position, &mut is_expanded, &mut update) {
+                Some(2) => {
+                    if follower.is_some() {
+                        follower = None;
+                    } else {
+                        lines.clear();
+                        position = i32::MAX;
+                        follower = Some(commands::follow("zpool", &["events", "-f", "-v"]));
+                    }
+                },
+                Some(3) => {
+                    let prompt = "The events of all pools will be cleared: ";
+                    let message = vec![String::from("zpool events -c")];
+
+                    if dialogs::confirmation_box("Clear Events", prompt, message) {
+                        pool_command_all(&["events", "-c"], "Error during zpool events -c");
+
+                        lines.clear();
+                        if follower.is_some() {
+                            follower = Some(commands::follow("zpool", &["events", "-f", "-v"]));
+                        }
+                    }
+                },
+                Some(_) => { },
+                None    => { break; },
+            }
+        }
+    }
+
+    const FEATURES: &[(&str, &str)] = &[
+        ("allocation_classes",       "Special and dedup allocation class vdevs"),
+        ("async_destroy",            "Destroy filesystems asynchronously in the background"),
+        ("block_cloning",            "Clone file blocks without copying data"),
+        ("blake3",                   "BLAKE3 checksum algorithm"),
+        ("bookmark_v2",              "Bookmarks of encrypted datasets"),
+        ("bookmark_written",         "Bookmarks that track the space written since them"),
+        ("bookmarks",                "Bookmarks as incremental send sources"),
+        ("device_rebuild",           "Sequential reconstruction of mirror and dRAID vdevs"),
+        ("device_removal",           "Removal of top-level vdevs"),
+        ("draid",                    "Distributed spare RAID vdevs"),
+        ("edonr",                    "Edon-R checksum algorithm"),
+        ("embedded_data",            "Very small blocks stored inside block pointers"),
+        ("empty_bpobj",              "Snapshots of empty block pointer lists take no space"),
+        ("enabled_txg",              "Record the transaction group in which features were enabled"),
+        ("encryption",               "Native dataset encryption"),
+        ("extensible_dataset",       "Datasets with extensible on-disk structures"),
+        ("filesystem_limits",        "filesystem_limit and snapshot_limit properties"),
+        ("head_errlog",              "Per-dataset error logs that survive dataset changes"),
+        ("hole_birth",               "Record the birth time of holes for faster incremental sends"),
+        ("large_blocks",             "Record sizes larger than 128K"),
+        ("large_dnode",              "Variable size dnodes for extended attributes"),
+        ("livelist",                 "Faster destruction of clones"),
+        ("log_spacemap",             "Log spacemap changes to reduce sync time"),
+        ("lz4_compress",             "LZ4 compression algorithm"),
+        ("multi_vdev_crash_dump",    "Crash dumps to multiple vdev pools"),
+        ("obsolete_counts",          "Track obsolete space of removed vdevs"),
+        ("project_quota",            "Project based space accounting and quotas"),
+        ("raidz_expansion",          "Adding disks to existing RAIDZ vdevs"),
+        ("redacted_datasets",        "Datasets received from redacted sends"),
+        ("redaction_bookmarks",      "Redaction bookmarks for redacted sends"),
+        ("resilver_defer",           "Defer new resilvers until the running one finishes"),
+        ("sha512",                   "SHA-512/256 checksum algorithm"),
+        ("skein",                    "Skein checksum algorithm"),
+        ("spacemap_histogram",       "Space map histograms for faster allocation"),
+        ("spacemap_v2",              "More efficient space map encoding"),
+        ("userobj_accounting",       "Per-user and per-group object accounting"),
+        ("vdev_zaps_v2",             "Per-vdev ZAP objects for root vdevs"),
+        ("zilsaxattr",               "Logging of extended attributes in the ZIL"),
+        ("zpool_checkpoint",         "Pool checkpoints"),
+        ("zstd_compress",            "Zstandard compression algorithm"),
+    ];
+
+    const COMPATIBILITY_WARNING: [&str; 4] = [
+        "WARNING: once enabled, features can not be disabled. The pool may",
+        "no longer be imported by older ZFS versions or other hosts, and may",
+        "become unbootable if the bootloader (e.g. GRUB) does not support",
+        "the new features.",
+    ];
+
+    fn feature_description(feature: &str) -> &'static str {
+
+        FEATURES.iter()
+            .find(|(name, _)| *name == feature)
+            .map(|(_, description)| *description)
+            .unwrap_or("")
+    }
+
+    pub fn pool_features(selected_value: &str) {
+
+        let title = format!("Pool Features: {}", selected_value);
+        let footnote = "ENTER Details  F2 Enable  F3 Upgrade  F4 Compatibility  F10 Close";
+
+        let mut position = 0;
+        let mut is_expanded = false;
+
+        loop {
+
+            let arguments = vec!["get", "-H", "-o", "property,value,source", "all", selected_value];
+            let result = commands::list("zpool", &arguments);
+
+            let compatibility = result.iter()
+                .map(|line| line.split('\t').collect::<Vec<&str>>())
+                .find(|fields| fields[0] == "compatibility")
+                .and_then(|fields| fields.get(1).map(|v| v.to_string()))
+                .unwrap_or_else(|| String::from("-"));
+
+            let features = result.iter()
+                .map(|line| line.split('\t').collect::<Vec<&str>>())
+                .filter(|fields| fields.len() >= 2 && fields[0].starts_with("feature@"))
+                .map(|fields| (fields[0].trim_start_matches("feature@").to_string(), fields[1].to_string()))
+                .collect::<Vec<(String, String)>>();
+
+            let mut entries = vec![(
+                format!("{:<24} {:<10} {}", "compatibility", compatibility, "Feature set the pool is restricted to"),
+                vec![String::from("Restricts the features that can be enabled (see /usr/share/zfs/compatibility.d)")],
+            )];
+
+            entries.extend(features.iter().map(|(name, state)| {
+                let description = feature_description(name);
+                let summary = format!("{:<24} {:<10} {}", name, state, description);
+                let details = vec![
+                    format!("feature@{}", name),
+                    format!("State: {}", state),
+                    String::from(description),
+                ];
+                (summary, details)
+            }));
+
+            let mut update = || entries.clone();
+
+            match dialogs::detail_box(&title, footnote, &mut position, &mut is_expanded, &mut update) {
+                Some(2) => {
+                    let feature = match features.get((position - 1).max(0) as usize) {
+                        Some((name, state)) if position > 0 && state == "disabled" => name.clone(),
+                        _ => continue,
+                    };
+
+                    let prompt = format!("Enable feature@{} on pool {}?", feature, selected_value);
+                    let message = COMPATIBILITY_WARNING.iter().map(|s| s.to_string()).collect();
+
+                    if dialogs::confirmation_box("Enable Feature", &prompt, message) {
+                        let property_value = format!("feature@{}=enabled", feature);
+                        pool_command(selected_value, &["set", &property_value], "Error during zpool set");
+                    }
+                },
+                Some(3) => {
+                    let prompt = format!("Enable all supported features on pool {}?", selected_value);
+                    let message = COMPATIBILITY_WARNING.iter().map(|s| s.to_string()).collect();
+
+                    if dialogs::confirmation_box("Upgrade Pool", &prompt, message) {
+                        let result = commands::list("zpool", &["upgrade", selected_value]);
+                        dialogs::message_box("Upgrade Pool", "Result of zpool upgrade", result);
+                    }
+                },
+                Some(4) => {
+                    let prompt = "Enter the compatibility feature set (off, legacy or file names): ";
+                    let value = dialogs::single_input_box("Pool Compatibility", prompt, compatibility.clone());
+
+                    if !value.is_empty() {
+                        let property_value = format!("compatibility={}", value);
+                        pool_command(selected_value, &["set", &property_value], "Error during zpool set");
+                    }
+                },
+                Some(_) => { },
+                None    => { break; },
+            }
+        }
+    }
+
+    fn importable_pools() -> Vec<(String, String)> {
+
+        let result = commands::list("zpool", &["import"]);
+        let mut pools: Vec<(String, String)> = Vec::new();
+
+        for line in result.iter() {
+            let line = line.trim();
+
+            if let Some(name) = line.strip_prefix("pool:") {
+                pools.push((name.trim().to_string(), String::new()));
+
+            } else if let Some(pool) = pools.last_mut() {
+                if let Some(id) = line.strip_prefix("id:") {
+                    pool.1.push_str(&format!("id {} ", id.trim()));
+                } else if let Some(state) = line.strip_prefix("state:") {
+                    pool.1.push_str(state.trim());
+                }
+            }
+        }
+
+        pools
+    }
+
+    fn recovery_step(arguments: &[&str], log: &mut Vec<String>) -> (bool, Vec<String>) {
+
+        let command = format!("zpool {}", arguments.join(" "));
+        commands::log(&format!("recovery: running {}", command));
+        log.push(format!("$ {}", command));
+
+        let (is_success, result) = commands::list_status("zpool", arguments);
+        let is_success = is_success && !result.iter().any(|line| line.starts_with("cannot"));
+        for line in result.iter() {
+            commands::log(&format!("recovery:   {}", line));
+        }
+        commands::log(&format!("recovery: {} {}", command, if is_success { "succeeded" } else { "failed" }));
+
+        if result.is_empty() {
+            log.push(String::from("  (no output)"));
+        }
+        log.extend(result.iter().map(|line| format!("  {}", line)));
+        log.push(format!("  ({})", if is_success { "succeeded" } else { "failed" }));
+
+        (is_success, result)
+    }
+
+    fn dry_run_prompt(arguments: &[&str], is_success: bool) -> String {
+
+        match is_success {
+            true  => format!("Dry run of zpool {} succeeded (nothing was changed):", arguments.join(" ")),
+            false => format!("Dry run of zpool {} failed, the import stays locked:", arguments.join(" ")),
+        }
+    }
+
+    pub fn pool_recovery() {
+
+        let title = "Pool Recovery Assistant";
+
+        let pools = importable_pools();
+        let mut items = pools.iter()
+            .map(|(name, info)| format!("{:<20} {}", name, info))
+            .collect::<Vec<String>>();
+        items.push(String::from("Enter a pool name or id..."));
+
+        let prompt = "Select the pool to recover (from zpool import):";
+        let pool = match dialogs::menu_box(title, prompt, items) {
+            Some(i) if i < pools.len() => pools[i].0.clone(),
+            Some(_) => dialogs::single_input_box(title, "Enter the pool name or id: ", String::new()),
+            None    => return,
+        };
+
+        if pool.is_empty() { return; }
+
+        let mut log: Vec<String> = Vec::new();
+        let mut dry_runs: Vec<String> = Vec::new();
+        let mut txg = String::new();
+
+        commands::log(&format!("recovery: assistant started for pool {}", pool));
+
+        let arguments = ["import", "-F", "-n", &pool];
+        let (is_success, result) = recovery_step(&arguments, &mut log);
+        if is_success {
+            dry_runs.push(String::from("-F"));
+        }
+        dialogs::presentation_box(title, &dry_run_prompt(&arguments, is_success), result);
+
+        loop {
+
+            let mut actions: Vec<(&str, String)> = vec![
+                ("-Fn",    String::from("Dry run of rewind (zpool import -F -n)")),
+            ];
+            if dry_runs.contains(&String::from("-F")) {
+                actions.push(("-F", String::from("Import discarding the last transactions (zpool import -F)")));
+            }
+            actions.push(("-FXn", String::from("Dry run of extreme rewind (zpool import -F -X -n)")));
+            if dry_runs.contains(&String::from("-FX")) {
+                actions.push(("-FX", String::from("Import with extreme rewind (zpool import -F -X)")));
+            }
+            actions.push(("-Tn", String::from("Dry run of rewind to a transaction group (zpool import -F -n -T txg)")));
+            if !txg.is_empty() && dry_runs.contains(&format!("-T {}", txg)) {
+                actions.push(("-T", format!("Import read-only at transaction group {} (zpool import -T)", txg)));
+            }
+            actions.push(("log", String::from("Show the log of this session")));
+
+            let items = actions.iter().map(|(_, item)| item.clone()).collect::<Vec<String>>();
+            let prompt = format!("Recovery options for pool {}:", pool);
+
+            let action = match dialogs::menu_box(title, &prompt, items) {
+                Some(i) => actions[i].0,
+                None    => break,
+            };
+
+            let dry_run = match action {
+                "-Fn"  => Some((String::from("-F"), vec!["import", "-F", "-n", &pool])),
+                "-FXn" => Some((String::from("-FX"), vec!["import", "-F", "-X", "-n", &pool])),
+                "-Tn"  => {
+                    let value = dialogs::single_input_box(title, "Enter the transaction group (txg): ", txg.clone());
+                    if value.parse::<u64>().is_err() { continue; }
+                    txg = value;
+                    Some((format!("-T {}", txg), vec!["import", "-F", "-n", "-T", &txg, &pool]))
+                },
+                _ => None,
+            };
+
+            if let Some((variant, arguments)) = dry_run {
+                let (is_success, result) = recovery_step(&arguments, &mut log);
+                dry_runs.retain(|dry_run| *dry_run != variant);
+                if is_success {
+                    dry_runs.push(variant);
+                }
+                dialogs::presentation_box(title, &dry_run_prompt(&arguments, is_success), result);
+                continue;
+            }
+
+            let arguments = match action {
+                "-F"  => vec!["import", "-F", &pool],
+                "-FX" => vec!["import", "-F", "-X", &pool],
+                "-T"  => vec!["import", "-o", "readonly=on", "-T", &txg, &pool],
+                _     => {
+                    dialogs::presentation_box(title, &format!("Session log (also in {}):", commands::LOG_FILE), log.clone());
+                    continue;
+                },
+            };
+
+            let prompt = format!("zpool {}", arguments.join(" "));
+            let message = vec![
+                String::from("The transactions reported by the dry run will be discarded"),
+                String::from("permanently. Consider importing read-only first."),
+            ];
+
+            if !dialogs::typed_confirmation_box(title, &prompt, message, &pool) {
+                commands::log(&format!("recovery: {} not confirmed", prompt));
+                continue;
+            }
+
+            let (is_success, result) = recovery_step(&arguments, &mut log);
+            let imported = is_success;
+
+            if imported {
+                commands::log(&format!("recovery: pool {} imported", pool));
+                log.push(format!("Pool {} imported.", pool));
+                dialogs::presentation_box(title, &format!("Session log (also in {}):", commands::LOG_FILE), log);
+                return;
+            }
+
+            dialogs::presentation_box(title, "The import failed:", result);
+        }
+
+        commands::log(&format!("recovery: assistant closed for pool {}", pool));
+    }
+
+    fn pool_command(selected_value: &str, arguments: &[&str], err_prompt: &str) {
+
+        let mut arguments = arguments.to_vec();
+        arguments.push(selected_value);
+        pool_command_all(&arguments, err_prompt);
+    }
+
+    fn pool_command_all(arguments: &[&str], err_prompt: &str) {
+
+        let err_title = "Error";
+        let result = commands::list("zpool", arguments);
+
+        if !result.is_empty() {
+            dialogs::message_box(err_title, err_prompt, result);
+        }
+    }
+    
+    pub fn pool_get_all(selected_value: &str) {
+
+        let title = "ZPOOL Get All";
+        let err_title = "Error";
+        let err_prompt = "Error during zpool set";
+
+        let arguments = vec!["get", "all", selected_value];
+        let result = commands::list("zpool", &arguments);    
+
+        let clone = result.clone().get(1..).unwrap().to_vec();
+        let legend = result.first().unwrap();
+
+        if let Ok(selection) = dialogs::navigation_box(title, legend, clone) {
+
+            let title = "ZPOOL Set";
+            let p_name: String;
+            let p_type: String;
+            let p_value: String;
+            let value: String;
+
+            if !selection.is_empty() {
+                let mut p = selection.split_whitespace();
+
+                p_name  = p.next().unwrap().to_string();
+                p_type  = p.next().unwrap().to_string();
+                p_value = p.next().unwrap().to_string();
+
+                let prompt = format!("{}:{}", p_name, p_type);
+                value = dialogs::single_input_box(title, &prompt, p_value);
+
+            } else {
+                let prompt = String::from("Enter property and value:");
+                let results = dialogs::dual_input_box(title, &prompt, "property".to_string(), "value".to_string());
+                p_type = results.0;
+                value = results.1;
+            }
+
+            if !value.is_empty() {
+                
+                let property_value = format!("{}={}", p_type, value);
+                let arguments = vec!["set", &property_value, &selected_value];
+                let result = commands::list("zpool", &arguments);
+            
+                if !result.is_empty() {
+                    dialogs::message_box(err_title, err_prompt, result);
+                }    
+            }
+        }
+    }
+}
+
+mod zfs {
+
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    use std::env;
+
+    use crate::commands;
+    use crate::config::{self, Config};
+    use crate::dialogs;
+    use crate::retention;
+    use super::human_size;
+    
+    pub fn volume_list() -> Vec<String> {
+        commands::list("zfs",   &["list", "-H", "-o", "name", "-t", "volume"])
+    }
+
+    pub fn volume_create(selected_value: &str) {
+    
+        let title = "Create Volume";
+        let prompt = "Enter the name and size of the new volume: ";
+        let default_value = selected_value.to_string();
+    
+        let err_title = "Error";
+        let err_prompt = "Error during zfs create";
+        let default_value2 = String::from("1g");
+    
+        let (new_dataset1, size) = dialogs::dual_input_box(title, prompt, default_value, default_value2);
+    
+        if !new_dataset1.is_empty() {
+            let arguments = vec!["create", "-V", &size, &new_dataset1];
+            let result = commands::list("zfs", &arguments);    
+    
+            if !result.is_empty() {
+                dialogs::message_box(err_title, err_prompt, result);
+            }
+        }
+    }
+    
+    pub fn dataset_list() -> Vec<String> {
+        commands::list("zfs",   &["list", "-H", "-o", "name", "-t", "filesystem"])
+    }
+
+    const DESTROY_OPTIONS: [(&str, &str); 3] = [
+        ("",   "Selected only"),
+        ("-r", "Recursive (-r)"),
+        ("-R", "Recursive with dependents (-R)"),
+    ];
+
+    fn destroy_preview(target: &str, flag: &str) -> (Vec<String>, bool) {
+
+        let mut arguments = vec!["destroy", "-n", "-v", "-p"];
+        if !flag.is_empty() { arguments.push(flag); }
+        arguments.push(target);
+
+        let result = commands::list("zfs", &arguments);
+        let dataset = target.split('@').next().unwrap_or(target);
+
+        let mut destroyed = Vec::new();
+        let mut reclaim = 0;
+        let mut errors = Vec::new();
+
+        for line in result.iter() {
+            match line.split_once('\t') {
+                Some(("destroy", name)) => {
+                    let is_dependent = name != dataset
+                        && !name.starts_with(&format!("{}/", dataset))
+                        && !name.starts_with(&format!("{}@", dataset));
+                    let note = if is_dependent { "  (dependent clone)" } else { "" };
+                    destroyed.push(format!("  {}{}", name, note));
+                },
+                Some(("reclaim", value)) => { reclaim = value.parse::<u64>().unwrap_or(0); },
+                _ => { errors.push(line.clone()); },
+            }
+        }
+
+        if !errors.is_empty() {
+            return (errors, false);
+        }
+
+        let mut preview = vec![
+            format!("{} dataset(s), snapshot(s) and clone(s) would be destroyed, reclaiming {}:",
+                destroyed.len(), human_size(reclaim)),
+            String::new(),
+        ];
+        preview.extend(destroyed);
+
+        (preview, true)
+    }
+
+    pub fn dataset_destroy(selected_value: &str) {
+    
+        let title = "Destroy Dataset";
+    
+        let err_title = "Error";
+        let err_prompt = "Error during zfs destroy";
+
+        let prompt = format!("Destroy {} (zfs destroy -nvp dry run):", selected_value);
+        let options = DESTROY_OPTIONS.iter().map(|(_, label)| *label).collect::<Vec<&str>>();
+        let mut is_possible = vec![false; options.len()];
+
+        let mut update = |option: usize| {
+            let (preview, possible) = destroy_preview(selected_value, DESTROY_OPTIONS[option].0);
+            is_possible[option] = possible;
+            preview
+        };
+
+        let option = match dialogs::option_box(title, &prompt, &options, &mut update) {
+            Some(option) => option,
+            None         => return,
+        };
+
+        if !is_possible[option] {
+            let message = vec![String::from("The dry run failed; select another option.")];
+            dialogs::message_box(err_title, "Unable to destroy", message);
+            return;
+        }
+
+        let flag = DESTROY_OPTIONS[option].0;
+        let prompt = format!("Confirm zfs {}", commands::zfs_destroy_arguments(selected_value, flag).join(" "));
+        let message = vec![String::from("The datasets listed in the preview will be destroyed.")];
+
+        if dialogs::confirmation_box(title, &prompt, message) {
+            let result = commands::zfs_destroy(selected_value, flag);
+
+            if !result.is_empty() {
+                let result = explain_holds(&[selected_value.to_string()], result);
+                dialogs::message_box(err_title, err_prompt, result);
+            }
+        }
+    }
+
+    pub fn snapshot_destroy(selected_value: &str, marked: &[String]) {
+
+        if marked.is_empty() {
+            dataset_destroy(selected_value);
+            return;
+        }
+
+        let is_range = marked.len() == 2 && dataset_of(&marked[0]) == dataset_of(&marked[1]);
+
+        if is_range {
+            snapshot_range_destroy(&marked[0], &marked[1]);
+        } else {
+            let mut message = vec![
+                String::from("Mark exactly two snapshots of the same dataset to destroy the range between them,"),
+                String::from("or clear the marks to destroy the selected snapshot. Marked snapshots:"),
+            ];
+            message.extend(marked.iter().map(|name| format!("  {}", name)));
+            dialogs::message_box("Error", "Unable to destroy the marked snapshots", message);
+        }
+    }
+
+    pub fn snapshot_range_destroy(first: &str, last: &str) {
+
+        let title = "Destroy Snapshot Range";
+        let err_title = "Error";
+        let err_prompt = "Error during zfs destroy";
+
+        let dataset = dataset_of(first).to_string();
+        let arguments = vec!["list", "-H", "-o", "name", "-t", "snapshot", "-s", "createtxg", "-d", "1", &dataset];
+        let snapshots = commands::list("zfs", &arguments);
+
+        let (a, b) = match (snapshots.iter().position(|s| s == first), snapshots.iter().position(|s| s == last)) {
+            (Some(a), Some(b)) => (a, b),
+            _ => {
+                let message = vec![format!("{} or {} no longer exists", first, last)];
+                dialogs::message_box(err_title, "Unable to destroy the range", message);
+                return;
+            },
+        };
+        let (first, last) = (a.min(b), a.max(b));
+
+        let short = |index: usize| snapshots.get(index).and_then(|s| s.split_once('@')).map(|(_, s)| s.to_string()).unwrap_or_default();
+        let range = |first: usize, last: usize| format!("{}@{}%{}", dataset, short(first), short(last));
+
+        let mut update = |first: usize, last: usize| {
+            let (mut preview, _) = destroy_preview(&range(first, last), "");
+            preview.insert(0, format!("zfs destroy {}", range(first, last)));
+            preview
+        };
+
+        let prompt = format!("Snapshots of {} in creation order:", dataset);
+        let (first, last) = match dialogs::range_box(title, &prompt, &snapshots, first, last, &mut update) {
+            Some(bounds) => bounds,
+            None         => return,
+        };
+
+        let target = range(first, last);
+        let prompt = format!("Destroy range {}@{}..{} ({} snapshots)? ", dataset, short(first), short(last), last - first + 1);
+        let message = snapshots[first..=last].to_vec();
+
+        if dialogs::confirmation_box(title, &prompt, message) {
+            let result = commands::list("zfs", &["destroy", &target]);
+
+            if !result.is_empty() {
+                let result = explain_holds(&snapshots[first..=last], result);
+                dialogs::message_box(err_title, err_prompt, result);
+            }
+        }
+    }
+
+    fn explain_holds(snapshots: &[String], result: Vec<String>) -> Vec<String> {
+
+        let is_busy = result.iter().any(|line| line.contains("dataset is busy"));
+        if !snapshots.iter().all(|name| name.contains('@')) || !is_busy {
+            return result;
+        }
+
+        let holds = snapshot_hold_list(snapshots, false);
+        if holds.is_empty() {
+            return result;
+        }
+
+        let mut explanation = Vec::new();
+        for snapshot in snapshots {
+            let tags = holds.iter()
+                .filter(|(name, _)| name == snapshot)
+                .map(|(_, tag)| tag.as_str())
+                .collect::<Vec<&str>>();
+            if !tags.is_empty() {
+                explanation.push(format!("{} can not be destroyed because it is held by:", snapshot));
+                explanation.push(format!("  {}", tags.join(", ")));
+            }
+        }
+        explanation.push(String::from("Release the holds with F4 Holds in Snapshot mode and try again."));
+
+        explanation
+    }
+    
+    pub fn dataset_create(selected_value: &str) {
+    
+        let title = "Create Dataset";
+        let prompt = "Enter the name of the new dataset: ";
+        let default_value = format!("{}/", selected_value);
+    
+        let err_title = "Error";
+        let err_prompt = "Error during zfs create";
+    
+        let new_dataset = dialogs::single_input_box(title, prompt, default_value);
+    
+        if !new_dataset.is_empty() {
+            let arguments = vec!["create", &new_dataset];
+            let result = commands::list("zfs", &arguments);    
+    
+            if !result.is_empty() {
+                dialogs::message_box(err_title, err_prompt, result);
+            }
+        }    
+    }
+    
+    pub fn dataset_snapshot(selected_value: &str) {
+    
+        let title = "Snapshot Dataset";
+        let prompt = "Enter the name of the new snapshot: ";
+        let template = Config::load().snapshot_template;
+        let default_value = format!("{}@{}", selected_value, commands::expand_template(&template));
+    
+        let err_title = "Error";
+        let err_prompt = "Error during zfs snapshot";
+
+        let mut fields = vec![
+            dialogs::Field::Text(String::from("Snapshot"), default_value),
+            dialogs::Field::Check(String::from("Recursive, snapshot all descendants (-r)"), false),
+            dialogs::Field::Text(String::from("User properties (-o), e.g. com.example:note=\"before upgrade\""), String::new()),
+        ];
+
+        if !dialogs::form_box(title, prompt, &mut fields) {
+            return;
+        }
+
+        if let [dialogs::Field::Text(_, new_dataset), dialogs::Field::Check(_, is_recursive), dialogs::Field::Text(_, properties)] = &fields[..] {
+            if new_dataset.is_empty() { return; }
+
+            let properties = match commands::split_pipeline(properties) {
+                _ if properties.trim().is_empty() => Vec::new(),
+                Ok(mut stages) if stages.len() == 1 => stages.remove(0),
+                Ok(_) => {
+                    let message = vec![String::from("Quote property values that contain |"), properties.clone()];
+                    dialogs::message_box(err_title, "Error parsing the user properties", message);
+                    return;
+                },
+                Err(error) => {
+                    dialogs::message_box(err_title, "Error parsing the user properties", vec![error]);
+                    return;
+                },
+            };
+            let result = commands::zfs_snapshot(new_dataset, *is_recursive, &properties);
+    
+            if !result.is_empty() {
+                dialogs::message_box(err_title, err_prompt, result);
+            }
+        }   
+    }
+    
+    pub fn dataset_rename(selected_value: &str) {
+    
+        let title = "Rename Dataset";
+        let prompt = "Enter the new name for the dataset: ";
+        let default_value = selected_value.to_string();
+    
+        let err_title = "Error";
+        let err_prompt = "Error during zfs rename";
+    
+        let new_dataset = dialogs::single_input_box(title, prompt, default_value);
+    
+        if !new_dataset.is_empty() {
+            let arguments = vec!["rename", selected_value, &new_dataset];
+            let result = commands::list("zfs", &arguments);    
+    
+            if !result.is_empty() {
+                dialogs::message_box(err_title, err_prompt, result);
+            }
+        }   
+    }
+    
+    pub fn dataset_promote(selected_value: &str) {
+    
+        let title = "Promote Dataset";
+        let prompt = "Promote the following dataset: ";
+        let default_value = vec![String::from(selected_value)];
+    
+        let err_title = "Error";
+        let err_prompt = "Error during zfs promote";
+    
+        let is_confirmed = dialogs::confirmation_box(title, prompt, default_value);
+    
+        if is_confirmed {
+            let arguments = vec!["promote", selected_value];
+            let result = commands::list("zfs", &arguments);    
+    
+            if !result.is_empty() {
+                dialogs::message_box(err_title, err_prompt, result);
+            }
+        }     
+    }
+    
+    pub fn dataset_get_all(selected_value: &str) {
+
+        let title = "ZFS Get All";
+        let err_title = "Error";
+        let err_prompt = "Error during zfs set";
+
+        let arguments = vec!["get", "all", selected_value];
+        let result = commands::list("zfs", &arguments);    
+
+        let clone = result.clone().get(1..).unwrap().to_vec();
+        let legend = result.first().unwrap();
+
+        if let Ok(selection) = dialogs::navigation_box(title, legend, clone) {
 
             let title = "ZFS Set";
             let p_name: String;
@@ -463,123 +2134,1171 @@ mod zfs {
             let p_value: String;
             let value: String;
 
-            if !selection.is_empty() {
-                let mut p = selection.split_whitespace();
+            if !selection.is_empty() {
+                let mut p = selection.split_whitespace();
+
+                p_name  = p.next().unwrap().to_string();
+                p_type  = p.next().unwrap().to_string();
+                p_value = p.next().unwrap().to_string();
+
+                let prompt = format!("{}:{}", p_name, p_type);
+                value = dialogs::single_input_box(title, &prompt, p_value);
+
+            } else {
+                let prompt = String::from("Enter property and value:");
+                let results = dialogs::dual_input_box(title, &prompt, "property".to_string(), "value".to_string());
+                p_type = results.0;
+                value = results.1;
+            }
+
+            if !value.is_empty() {
+
+                let property_value = format!("{}={}", p_type, value);
+                let arguments = vec!["set", &property_value, &selected_value];
+                let result = commands::list("zfs", &arguments);
+            
+                if !result.is_empty() {
+                    dialogs::message_box(err_title, err_prompt, result);
+                }    
+            }
+        }
+    }
+    
+    pub fn snapshot_list() -> Vec<String> {
+        commands::list("zfs",   &["list", "-H", "-o", "name", "-t", "snapshot"])
+    }
+
+    pub fn snapshot_clone(selected_value: &str) {
+    
+        let title = "Clone Snapshot";
+        let prompt = "Enter the name of the dataset to be cloned from snapshot: ";
+        let default_value = String::new();
+    
+        let err_title = "Error";
+        let err_prompt = "Error during zfs clone";
+    
+        let new_dataset = dialogs::single_input_box(title, prompt, default_value);
+    
+        if !new_dataset.is_empty() {
+            let arguments = vec!["clone", &selected_value, &new_dataset];
+            let result = commands::list("zfs", &arguments);    
+    
+            if !result.is_empty() {
+                dialogs::message_box(err_title, err_prompt, result);
+            }
+        }   
+    }
+    
+    const ROLLBACK_OPTIONS: [(&str, &str); 3] = [
+        ("",   "Latest snapshot only"),
+        ("-r", "Destroy newer snapshots (-r)"),
+        ("-R", "Destroy newer snapshots and clones (-R)"),
+    ];
+
+    fn rollback_preview(selected_value: &str, newer: &[String], clones: &[String], flag: &str) -> (Vec<String>, bool) {
+
+        let dataset = dataset_of(selected_value);
+        let short = selected_value.split_once('@').map(|(_, s)| s).unwrap_or("");
+
+        let property = format!("written@{}", short);
+        let arguments = vec!["get", "-H", "-p", "-o", "value", &property, dataset];
+        let written = commands::run("zfs", &arguments).trim().parse::<u64>().unwrap_or(0);
+
+        let mut preview = vec![
+            format!("{} will be rolled back to {}.", dataset, selected_value),
+            format!("{} written since the snapshot will be lost.", human_size(written)),
+            String::new(),
+        ];
+
+        let is_possible = match flag {
+            "" if !newer.is_empty() => {
+                preview.push(format!("zfs rollback would fail: {} newer snapshot(s) or bookmark(s) exist.", newer.len()));
+                preview.push(String::from("Select -r or -R to destroy them."));
+                false
+            },
+            "-r" if !clones.is_empty() => {
+                preview.push(format!("zfs rollback -r would fail: {} clone(s) depend on newer snapshots.", clones.len()));
+                preview.push(String::from("Select -R to destroy them."));
+                false
+            },
+            _ => true,
+        };
+
+        if !newer.is_empty() {
+            preview.push(String::new());
+            preview.push(format!("Newer snapshots and bookmarks ({}):", newer.len()));
+            preview.extend(newer.iter().map(|name| format!("  {}", name)));
+        }
+        if !clones.is_empty() {
+            preview.push(String::new());
+            preview.push(format!("Clones of newer snapshots ({}):", clones.len()));
+            preview.extend(clones.iter().map(|name| format!("  {}", name)));
+        }
+
+        (preview, is_possible)
+    }
+
+    pub fn snapshot_rollback(selected_value: &str) {
+    
+        let title = "Rollback to Snapshot";
+        let err_title = "Error";
+        let err_prompt = "Error during zfs rollback";
+
+        let dataset = dataset_of(selected_value);
+        let selected_txg = createtxg(selected_value);
+
+        let arguments = vec!["list", "-H", "-p", "-o", "name,createtxg", "-t", "snapshot,bookmark", "-s", "createtxg", "-d", "1", dataset];
+        let newer = commands::list("zfs", &arguments)
+            .iter()
+            .filter_map(|line| line.split_once('\t'))
+            .filter(|(name, txg)| *name != selected_value && txg.parse::<u64>().unwrap_or(0) > selected_txg)
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<String>>();
+
+        let mut clones = Vec::new();
+        let newer_snapshots = newer.iter().filter(|name| name.contains('@')).map(|s| s.as_str()).collect::<Vec<&str>>();
+
+        if !newer_snapshots.is_empty() {
+            let mut arguments = vec!["get", "-H", "-o", "value", "clones"];
+            arguments.extend(newer_snapshots);
+
+            clones = commands::list("zfs", &arguments)
+                .iter()
+                .flat_map(|line| line.split(',').map(|s| s.trim().to_string()).collect::<Vec<String>>())
+                .filter(|name| !name.is_empty() && name != "-")
+                .collect();
+        }
+
+        let prompt = format!("Rollback to {}:", selected_value);
+        let options = ROLLBACK_OPTIONS.iter().map(|(_, label)| *label).collect::<Vec<&str>>();
+        let mut is_possible = vec![false; options.len()];
+
+        let mut update = |option: usize| {
+            let (preview, possible) = rollback_preview(selected_value, &newer, &clones, ROLLBACK_OPTIONS[option].0);
+            is_possible[option] = possible;
+            preview
+        };
+
+        let option = match dialogs::option_box(title, &prompt, &options, &mut update) {
+            Some(option) => option,
+            None         => return,
+        };
+
+        if !is_possible[option] {
+            let message = vec![String::from("The rollback would fail; select another option.")];
+            dialogs::message_box(err_title, "Unable to rollback", message);
+            return;
+        }
+
+        let flag = ROLLBACK_OPTIONS[option].0;
+        let mut arguments = vec!["rollback"];
+        if !flag.is_empty() { arguments.push(flag); }
+        arguments.push(selected_value);
+
+        let prompt = format!("Confirm zfs {}", arguments.join(" "));
+        let message = vec![String::from("Changes since the snapshot and the items listed will be lost.")];
+
+        if dialogs::confirmation_box(title, &prompt, message) {
+            let result = commands::list("zfs", &arguments);    
+    
+            if !result.is_empty() {
+                dialogs::message_box(err_title, err_prompt, result);
+            }
+        }   
+    }
+    
+    pub fn snapshot_diff(selected_value: &str) {
+    
+        let title = "Diff Snapshot";
+        let prompt = format!("Compare snapshot {} with:", selected_value);
+        let default_value = selected_value.to_string();
+        let legend = "[ M modified | - removed | + created | R renamed ]";
+    
+        let new_dataset = dialogs::single_input_box(title, &prompt, default_value);
+    
+        if !new_dataset.is_empty() {
+            let arguments = vec!["diff", &selected_value, &new_dataset];
+            let result = commands::list("zfs", &arguments);    
+    
+            dialogs::presentation_box(title, legend, result);
+        }
+    }
+    
+    const SEND_OPTIONS: [(&str, &str); 8] = [
+        ("-R",      "Replication stream, all descendants and snapshots (-R)"),
+        ("-w",      "Raw stream, send encrypted data as is (-w)"),
+        ("-c",      "Compressed stream, keep on-disk compression (-c)"),
+        ("-L",      "Large blocks, allow blocks over 128K (-L)"),
+        ("-e",      "Embedded data, keep WRITE_EMBEDDED records (-e)"),
+        ("-h",      "Holds, include user holds (-h)"),
+        ("-p",      "Properties, include dataset properties (-p)"),
+        ("--saved", "Saved, send the partially received state of the filesystem (--saved)"),
+    ];
+
+    fn send_form(title: &str, prompt: &str, snapshot: &str) -> Option<(String, String, Vec<&'static str>)> {
+
+        let mut fields = vec![
+            dialogs::Field::Text(String::from("Snapshot"), snapshot.to_string()),
+            dialogs::Field::Text(String::from("Stream, commands separated by |"), String::from("zfs recv pool/dataset")),
+        ];
+        fields.extend(SEND_OPTIONS.iter().map(|(_, label)| dialogs::Field::Check(label.to_string(), false)));
+
+        if !dialogs::form_box(title, prompt, &mut fields) {
+            return None;
+        }
+
+        let (snapshot, stream) = match &fields[..2] {
+            [dialogs::Field::Text(_, snapshot), dialogs::Field::Text(_, stream)] => (snapshot.clone(), stream.clone()),
+            _ => return None,
+        };
+
+        let options = fields[2..].iter()
+            .zip(SEND_OPTIONS.iter())
+            .filter(|(field, _)| matches!(field, dialogs::Field::Check(_, true)))
+            .map(|(_, (flag, _))| *flag)
+            .collect::<Vec<&str>>();
+
+        if snapshot.is_empty() || stream.is_empty() {
+            return None;
+        }
+
+        Some((snapshot, stream, options))
+    }
+
+    pub fn snapshot_send(selected_value: &str) {
+    
+        let title = "Send Snapshot";
+        let prompt = "Enter the Snapshot and stream, and select the send options:";
+
+        let (mut send_snapshot, stream, options) = match send_form(title, prompt, selected_value) {
+            Some(form) => form,
+            None       => return,
+        };
+
+        let is_saved = options.contains(&"--saved");
+
+        let flag = if is_saved {
+            send_snapshot = dataset_of(&send_snapshot).to_string();
+            ""
+        } else {
+            let prompt = "Select the type of send stream:";
+            let items = vec![
+                String::from("Full stream"),
+                String::from("Incremental from a snapshot or bookmark (-i)"),
+                String::from("Incremental including intermediate snapshots (-I)"),
+            ];
+
+            match dialogs::menu_box(title, prompt, items) {
+                Some(0) => "",
+                Some(1) => "-i",
+                Some(2) => "-I",
+                _       => return,
+            }
+        };
+
+        let mut send_args = vec!["send"];
+        send_args.extend(options);
+
+        let source;
+        if !flag.is_empty() {
+            let sources = incremental_sources(&send_snapshot, flag == "-i");
+
+            if sources.is_empty() {
+                let message = vec![format!("{} has no older snapshots to send from", dataset_of(&send_snapshot))];
+                dialogs::message_box("Error", "Nothing to send", message);
+                return;
+            }
+
+            let prompt = format!("Incremental {} to {}. Select the source:", flag, send_snapshot);
+            source = match dialogs::menu_box(title, &prompt, sources.clone()) {
+                Some(i) => sources[i].clone(),
+                None    => return,
+            };
+
+            send_args.push(flag);
+            send_args.push(&source);
+        }
+        send_args.push(&send_snapshot);
+
+        confirm_send(title, send_args, &stream);
+    }
+
+    fn confirm_send(title: &str, send_args: Vec<&str>, stream: &str) {
+
+        let mut message = vec![
+            format!("zfs {} | {}", send_args.join(" "), stream),
+            String::new(),
+        ];
+
+        let estimate = send_estimate(&send_args);
+        match estimate {
+            Some(size) => message.push(format!("Estimated stream size: {}", human_size(size))),
+            None       => message.push(String::from("Estimated stream size: unknown")),
+        }
+
+        if let Some(pool) = receive_target(stream).map(|target| target.split('/').next().unwrap_or("").to_string()) {
+            let arguments = vec!["get", "-H", "-p", "-o", "value", "available", &pool];
+            if let Ok(available) = commands::run("zfs", &arguments).trim().parse::<u64>() {
+                let fits = match estimate {
+                    Some(size) if size > available => "does NOT fit",
+                    Some(_)                        => "fits",
+                    None                           => "unknown",
+                };
+                message.push(format!("Available in {}: {} ({})", pool, human_size(available), fits));
+            }
+        }
+
+        if dialogs::confirmation_box(title, "Run the following command?", message) {
+            send_to_stream(send_args, stream, estimate);
+        }
+    }
+
+    pub fn send_estimate(send_args: &[&str]) -> Option<u64> {
+
+        let mut arguments = vec!["send", "-n", "-v", "-P"];
+        arguments.extend(send_args.iter().skip(1));
+
+        commands::list("zfs", &arguments)
+            .iter()
+            .filter_map(|line| line.strip_prefix("size"))
+            .filter_map(|size| size.trim().parse::<u64>().ok())
+            .next_back()
+    }
+
+    fn receive_target(stream: &str) -> Option<String> {
+
+        let stages = commands::split_pipeline(stream).ok()?;
+        let words = stages.last()?.iter().map(|word| word.as_str()).collect::<Vec<&str>>();
+
+        match words[..] {
+            ["zfs", "recv", .., target] | ["zfs", "receive", .., target] if !target.starts_with('-') => Some(target.to_string()),
+            _ => None,
+        }
+    }
+
+    fn incremental_sources(snapshot: &str, with_bookmarks: bool) -> Vec<String> {
+
+        let snapshot_txg = createtxg(snapshot);
+        let types = if with_bookmarks { "snapshot,bookmark" } else { "snapshot" };
+
+        let arguments = vec!["list", "-H", "-p", "-o", "name,createtxg,type", "-t", types, "-d", "1", dataset_of(snapshot)];
+        let mut sources = commands::list("zfs", &arguments)
+            .iter()
+            .map(|line| line.split('\t').collect::<Vec<&str>>())
+            .filter(|fields| fields.len() >= 3)
+            .map(|fields| (fields[0].to_string(), fields[1].parse::<u64>().unwrap_or(0), fields[2] == "bookmark"))
+            .filter(|(_, txg, _)| *txg < snapshot_txg)
+            .collect::<Vec<(String, u64, bool)>>();
+
+        sources.sort_by_key(|(_, txg, is_bookmark)| (*is_bookmark, std::cmp::Reverse(*txg)));
+        sources.into_iter().map(|(name, _, _)| name).collect()
+    }
+
+    fn send_to_stream(send_args: Vec<&str>, stream: &str, estimate: Option<u64>) {
+        run_pipeline("Send Progress", "zfs", send_args, stream, estimate);
+    }
+
+    pub fn snapshot_send_file(selected_value: &str) {
+
+        let title = "Send to File";
+        let prompt = "Enter the Snapshot and file, and select the options:";
+        let directory = env::current_dir().map(|dir| dir.display().to_string()).unwrap_or_else(|_| String::from("/"));
+        let default_file = format!("{}/{}.zfs", directory.trim_end_matches('/'), selected_value.replace(['/', '@'], "_"));
+
+        let mut fields = vec![
+            dialogs::Field::Text(String::from("Snapshot"), selected_value.to_string()),
+            dialogs::Field::Text(String::from("File"), default_file),
+            dialogs::Field::Check(String::from("Compress with zstd (.zst)"), false),
+            dialogs::Field::Text(String::from("Split into parts of, e.g. 4G (empty for a single file)"), String::new()),
+        ];
+
+        if !dialogs::form_box(title, prompt, &mut fields) {
+            return;
+        }
+
+        let (snapshot, mut file, is_compressed, split) = match &fields[..] {
+            [dialogs::Field::Text(_, snapshot), dialogs::Field::Text(_, file),
+             dialogs::Field::Check(_, is_compressed), dialogs::Field::Text(_, split)] => {
+                (snapshot.clone(), file.clone(), *is_compressed, split.trim().to_string())
+            },
+            _ => return,
+        };
+
+        if snapshot.is_empty() || file.is_empty() {
+            return;
+        }
+        if is_compressed && !file.ends_with(".zst") {
+            file.push_str(".zst");
+        }
+
+        let part_size = match config::parse_size(&split) {
+            _ if split.is_empty() => None,
+            Some(size) if size > 0 => Some(size),
+            _ => {
+                dialogs::message_box("Error", "Invalid part size", vec![split]);
+                return;
+            },
+        };
+
+        let mut stages = Vec::new();
+        if is_compressed {
+            stages.push(String::from("zstd -c -3"));
+        }
+        match part_size {
+            Some(size) => stages.push(format!("split -b {} -d -a 3 - {}", size, commands::quote(&format!("{}.", file)))),
+            None       => stages.push(format!("dd {} bs=1M status=none", commands::quote(&format!("of={}", file)))),
+        }
+        let stream = stages.join(" | ");
+
+        let send_args = vec!["send", &snapshot];
+        let estimate = send_estimate(&send_args);
+
+        let mut message = vec![
+            format!("zfs {} | {}", send_args.join(" "), stream),
+            String::new(),
+            format!("Estimated stream size: {}", estimate.map(human_size).unwrap_or_else(|| String::from("unknown"))),
+        ];
+        if let (Some(size), Some(estimate)) = (part_size, estimate) {
+            let count = estimate.div_ceil(size).max(1);
+            if count > MAX_STREAM_PARTS {
+                let message = vec![
+                    format!("The estimated {} stream would need {} parts of {}", human_size(estimate), count, human_size(size)),
+                    format!("At most {} parts are supported, use a part size of at least {}",
+                            MAX_STREAM_PARTS, human_size(estimate.div_ceil(MAX_STREAM_PARTS))),
+                ];
+                dialogs::message_box("Error", "The part size is too small", message);
+                return;
+            }
+            message.push(format!("Parts before compression: {}", count));
+        }
+        if let (Some(size), None) = (part_size, estimate) {
+            message.push(format!("The stream size is unknown, at most {} parts of {} can be written", MAX_STREAM_PARTS, human_size(size)));
+        }
+        let manifest = format!("{}.manifest", file);
+        let old_parts = stream_file_parts(&file);
+        if std::path::Path::new(&file).exists() {
+            message.push(format!("{} already exists and will be overwritten", file));
+        }
+        if !old_parts.is_empty() || std::path::Path::new(&manifest).exists() {
+            message.push(format!("{} existing part(s) of {} and its manifest will be deleted", old_parts.len(), file));
+        }
+
+        if !dialogs::confirmation_box(title, "Run the following command?", message) {
+            return;
+        }
+
+        let directory = std::path::Path::new(&file).parent().map(|dir| dir.to_path_buf()).unwrap_or_default();
+        let old_files = old_parts.iter()
+            .map(|(name, _)| directory.join(name))
+            .chain(std::iter::once(std::path::PathBuf::from(&manifest)))
+            .filter(|path| path.exists());
+
+        for path in old_files {
+            if let Err(error) = std::fs::remove_file(&path) {
+                dialogs::message_box("Error", "Error deleting the existing parts", vec![format!("{}: {}", path.display(), error)]);
+                return;
+            }
+        }
+
+        let is_success = run_pipeline(title, "zfs", send_args, &stream, estimate);
+
+        if let (true, Some(size)) = (is_success, part_size) {
+            let parts = stream_file_parts(&file);
+            let is_complete = parts.iter().enumerate().all(|(i, (name, part))| {
+                name.ends_with(&format!(".{:03}", i)) && (*part == size || i == parts.len() - 1)
+            });
+
+            if !is_complete {
+                let message = vec![format!("The parts of {} do not match the split size; no manifest was written", file)];
+                dialogs::message_box("Error", "Error writing the parts manifest", message);
+                return;
+            }
+
+            let total = parts.iter().map(|(_, part)| part).sum::<u64>();
+            let mut lines = vec![format!("parts {}", parts.len()), format!("bytes {}", total)];
+            lines.extend(parts.iter().map(|(name, part)| format!("{} {}", name, part)));
+
+            if let Err(error) = std::fs::write(&manifest, lines.join("\n") + "\n") {
+                dialogs::message_box("Error", "Error writing the parts manifest", vec![error.to_string()]);
+            }
+        }
+    }
+
+    const MAX_STREAM_PARTS: u64 = 1000;
+
+    fn stream_file_parts(file: &str) -> Vec<(String, u64)> {
+
+        let path = std::path::Path::new(file);
+        let directory = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(std::path::Path::new("."));
+        let prefix = format!("{}.", path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default());
+
+        let mut parts = std::fs::read_dir(directory)
+            .map(|entries| entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| (entry.file_name().to_string_lossy().to_string(), entry.metadata().map(|m| m.len()).unwrap_or(0)))
+                .filter(|(name, _)| name.strip_prefix(&prefix)
+                    .map(|suffix| suffix.len() == 3 && suffix.chars().all(|c| c.is_ascii_digit()))
+                    .unwrap_or(false))
+                .collect::<Vec<(String, u64)>>())
+            .unwrap_or_default();
+
+        parts.sort();
+        parts
+    }
+
+    fn stream_parts(file: &str) -> Result<(String, Vec<String>, u64), Vec<String>> {
+
+        let is_part = |suffix: &str| suffix.len() == 3 && suffix.chars().all(|c| c.is_ascii_digit());
+        let base = match file.rsplit_once('.') {
+            Some((base, "manifest"))                 => base.to_string(),
+            Some((base, suffix)) if is_part(suffix) => base.to_string(),
+            _ => {
+                let size = std::fs::metadata(file).map(|m| m.len()).map_err(|error| vec![error.to_string()])?;
+                return Ok((file.to_string(), vec![file.to_string()], size));
+            },
+        };
+
+        let directory = std::path::Path::new(&base).parent().map(|dir| dir.display().to_string()).unwrap_or_default();
+        let parts = stream_file_parts(&base);
+        let mut problems = Vec::new();
+
+        if parts.is_empty() {
+            problems.push(format!("No parts of {} were found", base));
+        }
+        for (i, (name, _)) in parts.iter().enumerate() {
+            if !name.ends_with(&format!(".{:03}", i)) {
+                problems.push(format!("Part {:03} is missing before {}", i, name));
+                break;
+            }
+        }
+
+        if let Ok(manifest) = std::fs::read_to_string(format!("{}.manifest", base)) {
+            let entries = manifest.lines()
+                .filter_map(|line| line.split_once(' '))
+                .map(|(name, size)| (name.to_string(), size.trim().parse::<u64>().unwrap_or(0)))
+                .collect::<Vec<(String, u64)>>();
 
-                p_name  = p.next().unwrap().to_string();
-                p_type  = p.next().unwrap().to_string();
-                p_value = p.next().unwrap().to_string();
+            let header = |key: &str| entries.iter().find(|(name, _)| name == key).map(|(_, value)| *value);
+            let recorded = |key: &str| header(key).map(|value| value.to_string()).unwrap_or_else(|| String::from("none"));
+            let expected = entries.iter()
+                .filter(|(name, _)| name != "parts" && name != "bytes")
+                .cloned()
+                .collect::<Vec<(String, u64)>>();
 
-                let prompt = format!("{}:{}", p_name, p_type);
-                value = dialogs::single_input_box(title, &prompt, p_value);
+            let total = parts.iter().map(|(_, size)| size).sum::<u64>();
+            if header("parts") != Some(expected.len() as u64) {
+                problems.push(format!("The manifest lists {} parts but records {}", expected.len(), recorded("parts")));
+            }
+            if header("bytes") != Some(total) {
+                problems.push(format!("{} bytes found, the manifest records {}", total, recorded("bytes")));
+            }
 
-            } else {
-                let prompt = String::from("Enter property and value:");
-                let results = dialogs::dual_input_box(title, &prompt, "property".to_string(), "value".to_string());
-                p_type = results.0;
-                value = results.1;
+            for (name, size) in expected.iter() {
+                match parts.iter().find(|(part, _)| part == name) {
+                    None                                  => problems.push(format!("{} is missing", name)),
+                    Some((_, actual)) if actual != size => problems.push(format!("{} has {} bytes, expected {}", name, actual, size)),
+                    Some(_)                               => { },
+                }
             }
+            if parts.len() > expected.len() {
+                problems.push(format!("{} parts found, the manifest lists {}", parts.len(), expected.len()));
+            }
+        } else {
+            problems.push(format!("{}.manifest was not found, the parts cannot be verified", base));
+        }
 
-            if !value.is_empty() {
+        if !problems.is_empty() {
+            return Err(problems);
+        }
 
-                let property_value = format!("{}={}", p_type, value);
-                let arguments = vec!["set", &property_value, &selected_value];
-                let result = commands::list("zfs", &arguments);
-            
-                if !result.is_empty() {
-                    dialogs::message_box(err_title, err_prompt, result);
-                }    
+        let total = parts.iter().map(|(_, size)| size).sum::<u64>();
+        let parts = parts.into_iter()
+            .map(|(name, _)| std::path::Path::new(&directory).join(name).display().to_string())
+            .collect();
+
+        Ok((base, parts, total))
+    }
+
+    fn decompressor(file: &str) -> Option<&'static str> {
+
+        if      file.ends_with(".zst") { Some("zstd -d -c") }
+        else if file.ends_with(".gz")  { Some("gzip -d -c") }
+        else if file.ends_with(".xz")  { Some("xz -d -c")   }
+        else                           { None }
+    }
+
+    fn stream_header(parts: &[String], decompressor: Option<&str>) -> Vec<String> {
+
+        let mut stages = decompressor.map(|command| vec![command.to_string()]).unwrap_or_default();
+        stages.push(String::from("head -c 1048576"));
+        stages.push(String::from("zstream dump"));
+
+        let stages = match commands::split_pipeline(&stages.join(" | ")) {
+            Ok(stages) => stages,
+            Err(error) => return vec![error],
+        };
+
+        let arguments = parts.iter().map(|part| part.as_str()).collect::<Vec<&str>>();
+        let mut pipeline = match commands::piped("cat", arguments, &stages, 0, 1 << 20) {
+            Ok(pipeline) => pipeline,
+            Err(error)   => return vec![format!("Stream header unavailable: {}", error)],
+        };
+
+        let title = "Stream Header";
+        let footnote = "F10 Skip / Close";
+        let is_running = Cell::new(true);
+
+        loop {
+
+            let function_key = dialogs::guarded_progress_box(title, footnote, &|| is_running.get(), &mut || {
+                is_running.set(pipeline.is_running());
+                let state = match is_running.get() {
+                    true  => "Reading the stream header, press F10 to skip",
+                    false => "Stream header read, press ENTER to continue",
+                };
+                let mut lines = vec![String::from(state), String::new()];
+                lines.extend(pipeline.output());
+                (lines, None)
+            });
+
+            match function_key {
+                Some(10) => { return vec![String::from("Stream header skipped")]; },
+                Some(_)  => { },
+                None     => { break; },
             }
         }
+
+        if let Err(error) = pipeline.cancel() {
+            return vec![format!("Stream header unavailable: {}", error)];
+        }
+
+        pipeline.output()
     }
-    
-    pub fn snapshot_list() -> Vec<String> {
-        commands::list("zfs",   &["list", "-H", "-o", "name", "-t", "snapshot"])
+
+    pub fn dataset_receive(selected_value: &str) {
+
+        let title = "Receive from File";
+        let directory = env::current_dir().map(|dir| dir.display().to_string()).unwrap_or_else(|_| String::from("/"));
+
+        let file = match dialogs::file_box(title, &directory) {
+            Some(file) => file,
+            None       => return,
+        };
+
+        let (base, parts, total) = match stream_parts(&file) {
+            Ok(parts)     => parts,
+            Err(problems) => {
+                dialogs::message_box("Error", "The stream file is incomplete", problems);
+                return;
+            },
+        };
+
+        let decompressor = decompressor(&base);
+        let header = stream_header(&parts, decompressor);
+
+        let prompt = "Enter the target dataset and select the receive options:";
+        let mut fields = vec![
+            dialogs::Field::Text(String::from("Target dataset"), format!("{}/received", selected_value)),
+            dialogs::Field::Check(String::from("Force rollback of the target to its most recent snapshot (-F)"), false),
+            dialogs::Field::Check(String::from("Resumable, save a partially received state (-s)"), false),
+            dialogs::Field::Check(String::from("Do not mount the received dataset (-u)"), false),
+        ];
+
+        if !dialogs::form_box(title, prompt, &mut fields) {
+            return;
+        }
+
+        let (target, options) = match &fields[..] {
+            [dialogs::Field::Text(_, target), dialogs::Field::Check(_, is_forced),
+             dialogs::Field::Check(_, is_resumable), dialogs::Field::Check(_, is_unmounted)] => {
+                let options = [(*is_forced, " -F"), (*is_resumable, " -s"), (*is_unmounted, " -u")]
+                    .iter()
+                    .filter(|(is_set, _)| *is_set)
+                    .map(|(_, flag)| *flag)
+                    .collect::<String>();
+                (target.clone(), options)
+            },
+            _ => return,
+        };
+
+        if target.is_empty() {
+            return;
+        }
+
+        let mut stages = decompressor.map(|command| vec![command.to_string()]).unwrap_or_default();
+        stages.push(format!("zfs recv{} {}", options, commands::quote(&target)));
+        let stream = stages.join(" | ");
+
+        let mut message = vec![
+            format!("{} ({} in {} file(s))", base, human_size(total), parts.len()),
+            format!("| {}", stream),
+            String::new(),
+        ];
+        message.extend(header.into_iter().take(12));
+
+        if dialogs::confirmation_box(title, "Receive the following stream?", message) {
+            let arguments = parts.iter().map(|part| part.as_str()).collect::<Vec<&str>>();
+            run_pipeline(title, "cat", arguments, &stream, Some(total));
+        }
     }
 
-    pub fn snapshot_clone(selected_value: &str) {
-    
-        let title = "Clone Snapshot";
-        let prompt = "Enter the name of the dataset to be cloned from snapshot: ";
-        let default_value = format!("");
-    
+    fn run_pipeline(title: &str, first_cmd: &str, first_args: Vec<&str>, stream: &str, estimate: Option<u64>) -> bool {
+
+        let footnote = "F2 Rate limit  F3 Buffer size  F10 Cancel / Close";
+        let config = Config::load();
+
+        let stages = match commands::split_pipeline(stream) {
+            Ok(stages) => stages,
+            Err(error) => {
+                dialogs::message_box("Error", "Error parsing the stream pipeline", vec![error]);
+                return false;
+            },
+        };
+
+        let mut pipeline = match commands::piped(first_cmd, first_args.clone(), &stages,
+                                                 config.send_rate_limit, config.send_buffer_size) {
+            Ok(pipeline) => pipeline,
+            Err(error)   => {
+                dialogs::message_box("Error", "Error starting the pipeline", vec![error.to_string()]);
+                return false;
+            },
+        };
+
+        let command = format!("{} {} | {}", first_cmd, first_args.join(" "), stream);
+
+        let is_running = Cell::new(true);
+
+        loop {
+
+            let function_key = dialogs::guarded_progress_box(title, footnote, &|| is_running.get(), &mut || {
+                let progress = send_progress(&mut pipeline, &command, estimate);
+                is_running.set(pipeline.is_running());
+                progress
+            });
+
+            match function_key {
+                Some(10) => {
+                    let prompt = "The transfer is still running. Cancel it and stop every stage?";
+                    if dialogs::confirmation_box("Cancel Transfer", prompt, vec![command.clone()]) { break; }
+                },
+                Some(2) => {
+                    let prompt = "Enter the rate limit per second (e.g. 10M, 0 for unlimited): ";
+                    let value = dialogs::single_input_box("Rate Limit", prompt, human_size(pipeline.rate_limit()));
+                    if let Some(rate_limit) = config::parse_size(&value) {
+                        pipeline.set_rate_limit(rate_limit);
+                    }
+                },
+                Some(3) => {
+                    let prompt = "Enter the buffer size (e.g. 256M): ";
+                    let value = dialogs::single_input_box("Buffer Size", prompt, human_size(pipeline.buffer_size()));
+                    if let Some(buffer_size) = config::parse_size(&value) {
+                        pipeline.set_buffer_size(buffer_size);
+                    }
+                },
+                Some(_) => { },
+                None    => { break; },
+            }
+        }
+
+        let is_cancelled = pipeline.is_running();
+        let report = pipeline.report();
+        let is_success = !is_cancelled && pipeline.is_success();
+        let outcome = match (is_cancelled, is_success) {
+            (true, _)      => "Transfer cancelled",
+            (false, true)  => "Transfer completed",
+            (false, false) => "Transfer failed",
+        };
+
+        commands::log(&format!("{}: {} ({} transferred)", outcome, command, human_size(pipeline.transferred())));
+        dialogs::presentation_box(title, outcome, report);
+
+        is_success
+    }
+
+    fn send_progress(pipeline: &mut commands::Pipeline, command: &str, estimate: Option<u64>) -> (Vec<String>, Option<f32>) {
+
+        let is_running = pipeline.is_running();
+        let transferred = pipeline.transferred();
+        let seconds = pipeline.elapsed().as_secs().max(1);
+        let throughput = transferred / seconds;
+
+        let total = estimate.map(human_size).unwrap_or_else(|| String::from("unknown"));
+        let rate_limit = match pipeline.rate_limit() {
+            0     => String::from("unlimited"),
+            limit => format!("{}/s", human_size(limit)),
+        };
+        let eta = match estimate {
+            Some(size) if throughput > 0 && is_running => {
+                let remaining = size.saturating_sub(transferred) / throughput;
+                format!("{}:{:02}:{:02}", remaining / 3600, remaining / 60 % 60, remaining % 60)
+            },
+            _ => String::from("-"),
+        };
+        let state = match (is_running, pipeline.is_success()) {
+            (true, _)      => "Running, press F10 to cancel",
+            (false, true)  => "Completed, press ENTER to close",
+            (false, false) => "Failed, press ENTER to see the details",
+        };
+
+        let lines = vec![
+            command.to_string(),
+            String::new(),
+            format!("Transferred: {} of {}", human_size(transferred), total),
+            format!("Throughput:  {}/s", human_size(throughput)),
+            format!("ETA:         {}", eta),
+            format!("Rate limit:  {}", rate_limit),
+            format!("Buffer:      {} of {}", human_size(pipeline.buffered()), human_size(pipeline.buffer_size())),
+            String::new(),
+            state.to_string(),
+        ];
+        let percent = estimate.filter(|size| *size > 0).map(|size| transferred as f32 * 100.0 / size as f32);
+
+        (lines, percent)
+    }
+
+    pub fn snapshot_details(snapshots: &[String], policies: &[retention::Policy], prefixes: &[String]) -> Vec<String> {
+
+        let arguments = vec!["list", "-H", "-p", "-o", "name,userrefs", "-t", "snapshot"];
+        let userrefs = commands::list("zfs", &arguments)
+            .iter()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(name, refs)| (name.to_string(), refs.parse::<u64>().unwrap_or(0)))
+            .collect::<HashMap<String, u64>>();
+
+        let retention = retention::classify(policies, snapshots);
+
+        snapshots.iter()
+            .zip(retention.iter())
+            .map(|(name, retention)| {
+                let held = match userrefs.get(name) {
+                    Some(refs) if *refs > 0 => format!("held({})", refs),
+                    _                       => String::new(),
+                };
+                let auto = match retention::parse_name(name, prefixes) {
+                    Some(auto) if retention.is_empty() => format!("{} {}", auto.class, auto.timestamp),
+                    Some(auto)                         => auto.timestamp,
+                    None                               => String::new(),
+                };
+                format!("{} {} {}", auto, retention, held).trim().replace("  ", " ")
+            })
+            .collect()
+    }
+
+    pub fn snapshot_more(selected_value: &str, filter: &mut String, is_grouped: &mut bool) {
+
+        let title = "Snapshot Actions";
+        let prompt = "Select the action:";
+
+        let mut filters = vec![String::new(), String::from("manual")];
+        filters.extend(retention::CLASSES.iter().map(|(class, _)| class.to_string()));
+        filters.push(String::from("yearly"));
+        filters.push(String::from("znapzend"));
+
+        let mut items = vec![
+            String::from("Apply retention policy (dry run first)"),
+            String::from("Send to file"),
+            String::from(if *is_grouped { "Ungroup snapshots" } else { "Group snapshots by class" }),
+            String::from("Show all snapshots"),
+            String::from("Hide auto-snapshots (manual snapshots only)"),
+        ];
+        items.extend(filters.iter().skip(2).map(|class| format!("Show only {} snapshots", class)));
+
+        match dialogs::menu_box(title, prompt, items) {
+            Some(0) => { snapshot_apply_policy(selected_value); },
+            Some(1) => { snapshot_send_file(selected_value); },
+            Some(2) => { *is_grouped = !*is_grouped; },
+            Some(i) => { *filter = filters[i - 3].clone(); },
+            None    => { },
+        }
+    }
+
+    pub fn snapshot_apply_policy(selected_value: &str) {
+
+        let title = "Apply Retention Policy";
         let err_title = "Error";
-        let err_prompt = "Error during zfs clone";
-    
-        let new_dataset = dialogs::single_input_box(title, prompt, default_value);
-    
-        if !new_dataset.is_empty() {
-            let arguments = vec!["clone", &selected_value, &new_dataset];
-            let result = commands::list("zfs", &arguments);    
-    
+
+        let policies = retention::policies();
+        let dataset = dataset_of(selected_value);
+
+        if policies.is_empty() {
+            let message = vec![format!("Set com.zc: properties on a dataset or add policy lines to {}", crate::config::CONFIG_FILE)];
+            dialogs::message_box(title, "No retention policies configured", message);
+            return;
+        }
+
+        let policy = match policies.iter().find(|policy| policy.dataset == dataset) {
+            Some(policy) => policy.clone(),
+            None => {
+                let items = policies.iter().map(|policy| policy.describe()).collect::<Vec<String>>();
+                let prompt = format!("{} has no retention policy. Select the counts to apply to it:", dataset);
+
+                let counts = match dialogs::menu_box(title, &prompt, items) {
+                    Some(i) => policies[i].counts,
+                    None    => return,
+                };
+
+                let items = vec![format!("{} only", dataset), format!("{} and its descendants (-r)", dataset)];
+                let prompt = "Prune the snapshots of:";
+
+                match dialogs::menu_box(title, prompt, items) {
+                    Some(i) => retention::Policy { counts, is_recursive: i == 1, ..retention::Policy::new(dataset) },
+                    None    => return,
+                }
+            },
+        };
+
+        let expired = retention::expired(&policy, &retention::dataset_snapshots(&policy.dataset));
+
+        if expired.is_empty() {
+            let message = vec![policy.describe()];
+            dialogs::message_box(title, "No snapshots are due for pruning under the policy:", message);
+            return;
+        }
+
+        let shorts = expired.iter()
+            .filter_map(|name| name.split_once('@').map(|(_, short)| short))
+            .collect::<Vec<&str>>()
+            .join(",");
+        let target = format!("{}@{}", policy.dataset, shorts);
+        let flag = if policy.is_recursive { "-r" } else { "" };
+
+        let (mut preview, is_possible) = destroy_preview(&target, flag);
+        preview.insert(0, format!("Policy: {}", policy.describe()));
+        preview.insert(1, String::new());
+
+        let prompt = "Dry run of pruning the expired snapshots (zfs destroy -nvp):";
+        dialogs::presentation_box(title, prompt, preview);
+
+        if !is_possible {
+            return;
+        }
+
+        let prompt = format!("{} expired snapshot(s) of {} will be destroyed:", expired.len(), policy.dataset);
+        let message = vec![policy.describe()];
+
+        if dialogs::confirmation_box(title, &prompt, message) {
+            retention::prune(&policy, false);
+
+            let remaining = retention::expired(&policy, &retention::dataset_snapshots(&policy.dataset));
+            if !remaining.is_empty() {
+                let message = vec![format!("{} snapshot(s) could not be destroyed, see {}", remaining.len(), commands::LOG_FILE)];
+                dialogs::message_box(err_title, "Error while pruning", message);
+            }
+        }
+    }
+
+    fn snapshot_hold_list(snapshots: &[String], is_recursive: bool) -> Vec<(String, String)> {
+
+        let mut arguments = vec!["holds", "-H"];
+        if is_recursive { arguments.push("-r"); }
+        arguments.extend(snapshots.iter().map(|s| s.as_str()));
+
+        commands::list("zfs", &arguments)
+            .iter()
+            .map(|line| line.split('\t').collect::<Vec<&str>>())
+            .filter(|fields| fields.len() >= 2)
+            .map(|fields| (fields[0].to_string(), fields[1].to_string()))
+            .collect()
+    }
+
+    pub fn snapshot_holds(selected_value: &str, marked: &[String]) {
+
+        let title = "Snapshot Holds";
+        let footnote = "F2 Hold  F3 Release  F7 Find  F10 Close";
+        let err_title = "Error";
+
+        let snapshots = if marked.is_empty() { vec![selected_value.to_string()] } else { marked.to_vec() };
+
+        loop {
+
+            let mut arguments = vec!["holds", "-r"];
+            arguments.extend(snapshots.iter().map(|s| s.as_str()));
+            let result = commands::list("zfs", &arguments);
+
+            let prompt = format!("Holds of {} snapshot(s), including descendants (zfs holds -r):", snapshots.len());
+
+            match dialogs::presentation_box_with_keys(title, &prompt, footnote, result, &[2, 3]) {
+                Some(2) => {
+                    let tag = dialogs::single_input_box("Hold Snapshots", "Enter the tag of the new hold: ", String::from("keep"));
+                    if tag.is_empty() { continue; }
+
+                    let items = vec![
+                        String::from("Hold the selected snapshots only"),
+                        String::from("Hold the selected snapshots and their descendants (-r)"),
+                    ];
+                    let mut arguments = match dialogs::menu_box("Hold Snapshots", "Apply the hold to:", items) {
+                        Some(0) => vec!["hold", &tag],
+                        Some(_) => vec!["hold", "-r", &tag],
+                        None    => continue,
+                    };
+                    arguments.extend(snapshots.iter().map(|s| s.as_str()));
+
+                    let result = commands::list("zfs", &arguments);
+                    if !result.is_empty() {
+                        dialogs::message_box(err_title, "Error during zfs hold", result);
+                    }
+                },
+                Some(3) => {
+                    let holds = snapshot_hold_list(&snapshots, true);
+                    let mut tags = holds.iter().map(|(_, tag)| tag.clone()).collect::<Vec<String>>();
+                    tags.sort();
+                    tags.dedup();
+
+                    let tag = match dialogs::menu_box("Release Holds", "Select the tag to release:", tags.clone()) {
+                        Some(i) => tags[i].clone(),
+                        None    => continue,
+                    };
+
+                    let held = holds.iter()
+                        .filter(|(_, t)| *t == tag)
+                        .map(|(snapshot, _)| snapshot.clone())
+                        .collect::<Vec<String>>();
+
+                    let prompt = format!("The hold '{}' will be released from:", tag);
+                    if dialogs::confirmation_box("Release Holds", &prompt, held.clone()) {
+                        let mut errors = Vec::new();
+
+                        for snapshot in held.iter() {
+                            errors.extend(commands::list("zfs", &["release", &tag, snapshot]));
+                        }
+                        if !errors.is_empty() {
+                            dialogs::message_box(err_title, "Error during zfs release", errors);
+                        }
+                    }
+                },
+                _ => { break; },
+            }
+        }
+    }
+
+    fn createtxg(selected_value: &str) -> u64 {
+
+        let arguments = vec!["get", "-H", "-p", "-o", "value", "createtxg", selected_value];
+        commands::run("zfs", &arguments).trim().parse::<u64>().unwrap_or(0)
+    }
+
+    fn dataset_of(selected_value: &str) -> &str {
+        selected_value.split(['@', '#']).next().unwrap_or(selected_value)
+    }
+
+    pub fn bookmark_list() -> Vec<String> {
+        commands::list("zfs",   &["list", "-H", "-o", "name", "-t", "bookmark"])
+    }
+
+    pub fn snapshot_bookmark(selected_value: &str) {
+
+        let title = "Bookmark Snapshot";
+        let prompt = "Enter the name of the new bookmark: ";
+        let default_value = selected_value.replacen('@', "#", 1);
+
+        let err_title = "Error";
+        let err_prompt = "Error during zfs bookmark";
+
+        let new_bookmark = dialogs::single_input_box(title, prompt, default_value);
+
+        if !new_bookmark.is_empty() {
+            let arguments = vec!["bookmark", selected_value, &new_bookmark];
+            let result = commands::list("zfs", &arguments);
+
             if !result.is_empty() {
                 dialogs::message_box(err_title, err_prompt, result);
             }
-        }   
+        }
     }
-    
-    pub fn snapshot_rollback(selected_value: &str) {
-    
-        let title = "Rollback to Snapshot";
-        let prompt = "The dataset will be rolled-back to the following snapshot: ";
+
+    pub fn bookmark_destroy(selected_value: &str) {
+
+        let title = "Destroy Bookmark";
+        let prompt = "The following bookmark will be destroyed: ";
         let message = vec![String::from(selected_value)];
-    
+
         let err_title = "Error";
-        let err_prompt = "Error during zfs rollback";
-    
-        let is_confirmed = dialogs::confirmation_box(title, prompt, message);
-    
-        if is_confirmed {
-            let arguments = vec!["rollback", selected_value];
-            let result = commands::list("zfs", &arguments);    
-    
+        let err_prompt = "Error during zfs destroy";
+
+        if dialogs::confirmation_box(title, prompt, message) {
+            let result = commands::zfs_destroy(selected_value, "");
+
             if !result.is_empty() {
                 dialogs::message_box(err_title, err_prompt, result);
             }
-        }   
-    }
-    
-    pub fn snapshot_diff(selected_value: &str) {
-    
-        let title = "Diff Snapshot";
-        let prompt = format!("Compare snapshot {} with:", selected_value);
-        let default_value = format!("{}", selected_value);
-        let legend = "[ M modified | - removed | + created | R renamed ]";
-    
-        let new_dataset = dialogs::single_input_box(title, &prompt, default_value);
-    
-        if !new_dataset.is_empty() {
-            let arguments = vec!["diff", &selected_value, &new_dataset];
-            let result = commands::list("zfs", &arguments);    
-    
-            dialogs::presentation_box(title, legend, result);
         }
     }
-    
-    pub fn snapshot_send(selected_value: &str) {
-    
-        let title = "Send Snapshot";
-        let prompt = "Enter the Snapshot and stream to send: ";
-        let default_value = format!("{}", selected_value);
-    
+
+    pub fn bookmark_rename(selected_value: &str) {
+
+        let title = "Rename Bookmark";
+        let prompt = "Enter the new name for the bookmark: ";
+        let default_value = selected_value.to_string();
+
         let err_title = "Error";
-        let err_prompt = "Error during zfs send";
-        let default_value2 = format!("zfs recv pool/dataset");
-    
-        let (send_snapshot, stream) = dialogs::dual_input_box(title, prompt, default_value, default_value2);
-    
-        if !send_snapshot.is_empty() {
-            let send_args = vec!["send", &send_snapshot];
-            let stream_str: Vec<&str> = stream.split_whitespace().collect();
 
-            let recv_cmd = stream_str.get(0).unwrap();
-            let recv_args = stream_str.get(1..).unwrap().to_vec();
+        let new_bookmark = dialogs::single_input_box(title, prompt, default_value);
+
+        if !new_bookmark.is_empty() && new_bookmark != selected_value {
+            let arguments = vec!["bookmark", selected_value, &new_bookmark];
+            let result = commands::list("zfs", &arguments);
 
-            let result = commands::piped("zfs", send_args, recv_cmd, recv_args);    
-    
             if !result.is_empty() {
-                dialogs::message_box(err_title, err_prompt, result);
+                dialogs::message_box(err_title, "Error during zfs bookmark", result);
+                return;
+            }
+
+            let arguments = vec!["destroy", selected_value];
+            let result = commands::list("zfs", &arguments);
+
+            if !result.is_empty() {
+                dialogs::message_box(err_title, "Error during zfs destroy", result);
             }
         }
     }
+
+    pub fn bookmark_send(selected_value: &str) {
+
+        let title = "Send from Bookmark";
+        let err_title = "Error";
+
+        let dataset = dataset_of(selected_value);
+        let bookmark_txg = createtxg(selected_value);
+
+        let arguments = vec!["list", "-H", "-p", "-o", "name,createtxg", "-t", "snapshot", "-s", "createtxg", "-d", "1", dataset];
+        let snapshots = commands::list("zfs", &arguments)
+            .iter()
+            .filter_map(|line| line.split_once('\t'))
+            .filter(|(_, txg)| txg.parse::<u64>().unwrap_or(0) > bookmark_txg)
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<String>>();
+
+        if snapshots.is_empty() {
+            let message = vec![format!("{} has no snapshots newer than {}", dataset, selected_value)];
+            dialogs::message_box(err_title, "Nothing to send", message);
+            return;
+        }
+
+        let prompt = format!("Incremental source: {}. Select the snapshot to send:", selected_value);
+        let mut items = snapshots;
+        items.reverse();
+
+        let snapshot = match dialogs::menu_box(title, &prompt, items.clone()) {
+            Some(i) => items[i].clone(),
+            None    => return,
+        };
+
+        let prompt = format!("Incremental from {}. Enter the stream and select the send options:", selected_value);
+        let (snapshot, stream, options) = match send_form(title, &prompt, &snapshot) {
+            Some(form) => form,
+            None       => return,
+        };
+
+        if options.contains(&"--saved") {
+            let message = vec![String::from("--saved sends a partially received filesystem and can not start from a bookmark")];
+            dialogs::message_box(err_title, "Invalid send options", message);
+            return;
+        }
+
+        let mut send_args = vec!["send"];
+        send_args.extend(options);
+        send_args.extend(["-i", selected_value, &snapshot]);
+
+        confirm_send(title, send_args, &stream);
+    }
 }
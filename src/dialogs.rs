@@ -1,4 +1,7 @@
 
+use std::fs;
+use std::path::Path;
+
 use ncurses::*;
 use crate::contents::{ Content, ContentType };
 
@@ -11,6 +14,9 @@ const SPACE: &str = "
 const KEY_TAB:   i32 = 0x009;
 const KEY_ENTER: i32 = 0x00a;
 const KEY_ESC:   i32 = 0x1b;
+const KEY_SPACE: i32 = 0x020;
+
+const REFRESH_INTERVAL: i32 = 1000;
 
 pub fn initialize() {
 
@@ -39,11 +45,13 @@ pub fn screen_dimensions() -> (i32, i32) {
     (max_y, max_x)
 }
 
-pub fn dual_pane(left_content: &mut Content, right_content: &mut Content) {
+pub fn dual_pane(left_content: &mut Content, right_content: &mut Content, status: &[String]) {
 
-    let (height, max_x) = screen_dimensions();
+    let (max_y, max_x) = screen_dimensions();
     let mut width;
 
+    let height = if status.is_empty() { max_y } else { max_y - 1 };
+
     width = max_x / 2;
     let left_pane = window(height - 1, width, 0, 0, &left_content.title());
     left_content.scroll(height - 2);
@@ -54,6 +62,13 @@ pub fn dual_pane(left_content: &mut Content, right_content: &mut Content) {
     right_content.scroll(height - 2);
     write_at(right_pane, right_content, height - 3, width);
 
+    if !status.is_empty() {
+        let line = fit_to_window(&format!(" ! {}", status.join("  ! ")), max_x as usize + 2);
+        attron(A_REVERSE() | A_BOLD());
+        mvprintw(max_y - 2, 0, &line);
+        attroff(A_REVERSE() | A_BOLD());
+    }
+
     refresh();
     wrefresh(left_pane);
     wrefresh(right_pane);
@@ -86,6 +101,8 @@ pub fn handle_keys(left: &mut Content, right: &mut Content) -> bool {
         KEY_HOME  => { selected_content.jump_to(0); },
         KEY_END   => { selected_content.jump_to_last(); },
         KEY_TAB   => { selected_content.next(); },
+        KEY_IC    => { selected_content.toggle_mark(); },
+        KEY_SPACE => { selected_content.toggle_mark(); },
 
         KEY_F1    => { selected_content.key_f(1); },
         KEY_F2    => { selected_content.key_f(2); },
@@ -108,48 +125,209 @@ pub fn handle_keys(left: &mut Content, right: &mut Content) -> bool {
 
 pub fn presentation_box(title: &str, prompt: &str, message: Vec<String>) {
 
+    let footnote = "F7 Find  F10 Close";
+    presentation_box_with_keys(title, prompt, footnote, message, &[]);
+}
+
+pub fn presentation_box_with_keys(title: &str, prompt: &str, footnote: &str,
+                                  message: Vec<String>, function_keys: &[i32]) -> Option<i32> {
+
     let (max_y, max_x) = screen_dimensions();
     let (height, width) = (max_y - 6, max_x - 8);
     let (start_y, start_x) = center_window(height, width);
-    let footnote = "Press F10 to close";
     let dialog = window(height, width, start_y, start_x, title);
+    let visible = height - 6;
 
     mvwprintw(dialog, 2, 3, prompt);
     write_footnote(dialog, height, width, footnote);
 
     let mut start_from = 0;
+    let mut search = String::new();
+
     loop {
 
-        if start_from < 0 || message.len() < height as usize {
-            start_from = 0;
+        if start_from > message.len() as i32 - visible {
+            start_from = message.len() as i32 - visible;
+        }
 
-        } else if start_from >= message.len() as i32 - (height - 6) {
-            start_from = message.len() as i32 - (height - 6);
+        if start_from < 0 {
+            start_from = 0;
         }
 
-        for (i, line) in message.iter().enumerate() {
+        for i in 0..visible {
 
-            if (i as i32) < start_from { continue }
-            if (i as i32) >= height + start_from - 6 { break }
+            let line = message.get((i + start_from) as usize).map(|s| s.as_str()).unwrap_or("");
+            let is_match = !search.is_empty() && line.contains(&search);
 
+            if is_match { wattron(dialog, A_REVERSE()); }
             let text = fit_to_window(line, width as usize - 6);
-            mvwprintw(dialog, 3+(i as i32)-start_from, 3, &text);
+            mvwprintw(dialog, 3 + i, 3, &text);
+            wattroff(dialog, A_REVERSE());
         }
 
+        touchwin(dialog);
         wrefresh(dialog);
 
         let key = getch();
         match key {
-            KEY_ENTER | KEY_ESC | KEY_F10 => { delwin(dialog); return; },
+            KEY_ENTER | KEY_ESC | KEY_F10 => { delwin(dialog); return None; },
             KEY_UP    => { start_from -= 1; },
             KEY_DOWN  => { start_from += 1; },
             KEY_PPAGE => { start_from -= 10; },
             KEY_NPAGE => { start_from += 10; },
-            _ => { },
+            KEY_HOME  => { start_from = 0; },
+            KEY_END   => { start_from = message.len() as i32; },
+            KEY_F7    => {
+                let value = single_input_box("Find", "Enter the text to search for: ", search.clone());
+                if value.is_empty() { search.clear(); continue; }
+
+                let from = if value == search { start_from + 1 } else { start_from };
+                search = value;
+
+                let found = message.iter()
+                    .enumerate()
+                    .skip(from as usize)
+                    .chain(message.iter().enumerate().take(from as usize))
+                    .find(|(_, line)| line.contains(&search));
+
+                if let Some((i, _)) = found { start_from = i as i32; }
+            },
+            _ => {
+                if let Some(n) = function_key(key) {
+                    if function_keys.contains(&n) { delwin(dialog); return Some(n); }
+                }
+            },
         }
     } 
 }
 
+pub fn progress_box(title: &str, footnote: &str,
+                    update: &mut dyn FnMut() -> (Vec<String>, Option<f32>)) -> Option<i32> {
+
+    guarded_progress_box(title, footnote, &|| false, update)
+}
+
+pub fn guarded_progress_box(title: &str, footnote: &str, is_busy: &dyn Fn() -> bool,
+                            update: &mut dyn FnMut() -> (Vec<String>, Option<f32>)) -> Option<i32> {
+
+    let (max_y, max_x) = screen_dimensions();
+    let (height, width) = (max_y - 6, max_x - 8);
+    let (start_y, start_x) = center_window(height, width);
+    let dialog = window(height, width, start_y, start_x, title);
+
+    write_footnote(dialog, height, width, footnote);
+    timeout(REFRESH_INTERVAL);
+
+    let result = loop {
+
+        let (message, percent) = update();
+
+        let bar = match percent {
+            Some(percent) => progress_bar(percent, (width as usize).saturating_sub(20)),
+            None          => String::new(),
+        };
+        mvwprintw(dialog, 2, 3, &fit_to_window(&bar, width as usize - 6));
+
+        for i in 0..(height - 8) {
+            let line = message.get(i as usize).map(|s| s.as_str()).unwrap_or("");
+            let text = fit_to_window(line, width as usize - 6);
+            mvwprintw(dialog, 4 + i, 3, &text);
+        }
+
+        wrefresh(dialog);
+
+        let key = getch();
+        match key {
+            ERR                                      => { },
+            KEY_ENTER | KEY_ESC if is_busy()         => { },
+            KEY_F10 if is_busy()                     => { break Some(10); },
+            KEY_ENTER | KEY_ESC | KEY_F10            => { break None; },
+            _ => {
+                if let Some(n) = function_key(key) { break Some(n); }
+            },
+        }
+    };
+
+    timeout(-1);
+    delwin(dialog);
+    result
+}
+
+pub fn live_box(title: &str, footnote: &str, update: &mut dyn FnMut() -> Vec<String>) -> Option<i32> {
+
+    progress_box(title, footnote, &mut || (update(), None))
+}
+
+pub fn detail_box(title: &str, footnote: &str, position: &mut i32, is_expanded: &mut bool,
+                  update: &mut dyn FnMut() -> Vec<(String, Vec<String>)>) -> Option<i32> {
+
+    let (max_y, max_x) = screen_dimensions();
+    let (height, width) = (max_y - 6, max_x - 8);
+    let (start_y, start_x) = center_window(height, width);
+    let dialog = window(height, width, start_y, start_x, title);
+
+    write_footnote(dialog, height, width, footnote);
+    timeout(REFRESH_INTERVAL);
+
+    let mut start_from = 0;
+    let mut previous_len = 0;
+
+    let result = loop {
+
+        let entries = update();
+        let rows = height - 6;
+        let list_rows = if *is_expanded { rows / 2 } else { rows };
+
+        if *position == previous_len - 1 { *position = entries.len() as i32 - 1; }
+        previous_len = entries.len() as i32;
+
+        *position = (*position).clamp(0, (entries.len() as i32 - 1).max(0));
+        if *position < start_from               { start_from = *position; }
+        if *position >= start_from + list_rows  { start_from = *position - list_rows + 1; }
+
+        for i in 0..list_rows {
+            let index = i + start_from;
+            let line = entries.get(index as usize).map(|e| e.0.as_str()).unwrap_or("");
+
+            if index == *position { wattron(dialog, A_REVERSE()); }
+            mvwprintw(dialog, 2 + i, 3, &fit_to_window(line, width as usize - 6));
+            wattroff(dialog, A_REVERSE());
+        }
+
+        if *is_expanded {
+            let details = entries.get(*position as usize).map(|e| e.1.clone()).unwrap_or_default();
+            mvwprintw(dialog, 2 + list_rows, 3, &fit_to_window(BAR, width as usize - 6));
+
+            for i in 0..(rows - list_rows - 1) {
+                let line = details.get(i as usize).map(|s| s.as_str()).unwrap_or("");
+                mvwprintw(dialog, 3 + list_rows + i, 3, &fit_to_window(line, width as usize - 6));
+            }
+        }
+
+        wrefresh(dialog);
+
+        let key = getch();
+        match key {
+            ERR               => { },
+            KEY_ENTER         => { *is_expanded = !*is_expanded; },
+            KEY_ESC | KEY_F10 => { break None; },
+            KEY_UP            => { *position -= 1; },
+            KEY_DOWN          => { *position += 1; },
+            KEY_PPAGE         => { *position -= 10; },
+            KEY_NPAGE         => { *position += 10; },
+            KEY_HOME          => { *position = 0; },
+            KEY_END           => { *position = entries.len() as i32; },
+            _ => {
+                if let Some(n) = function_key(key) { break Some(n); }
+            },
+        }
+    };
+
+    timeout(-1);
+    delwin(dialog);
+    result
+}
+
 pub fn navigation_box(title: &str, prompt: &str, message: Vec<String>) -> Result<String,()> {
 
     let (max_y, max_x) = screen_dimensions();
@@ -220,6 +398,198 @@ pub fn navigation_box(title: &str, prompt: &str, message: Vec<String>) -> Result
     } 
 }
 
+pub fn option_box(title: &str, prompt: &str, options: &[&str],
+                  update: &mut dyn FnMut(usize) -> Vec<String>) -> Option<usize> {
+
+    let (max_y, max_x) = screen_dimensions();
+    let (height, width) = (max_y - 6, max_x - 8);
+    let (start_y, start_x) = center_window(height, width);
+    let footnote = "LEFT/RIGHT Option  ENTER Confirm  F10 Cancel";
+    let dialog = window(height, width, start_y, start_x, title);
+    let visible = height - 8;
+
+    mvwprintw(dialog, 2, 3, prompt);
+    write_footnote(dialog, height, width, footnote);
+
+    let mut selected: usize = 0;
+    let mut message = update(selected);
+    let mut start_from: i32 = 0;
+
+    loop {
+
+        let mut x = 3;
+        for (i, option) in options.iter().enumerate() {
+            if i == selected { wattron(dialog, A_REVERSE()); }
+            mvwprintw(dialog, 3, x, &format!(" {} ", option));
+            wattroff(dialog, A_REVERSE());
+            x += option.len() as i32 + 3;
+        }
+
+        start_from = start_from.clamp(0, (message.len() as i32 - visible).max(0));
+
+        for i in 0..visible {
+            let line = message.get((i + start_from) as usize).map(|s| s.as_str()).unwrap_or("");
+            mvwprintw(dialog, 5 + i, 3, &fit_to_window(line, width as usize - 6));
+        }
+
+        refresh();
+        wrefresh(dialog);
+
+        let key = getch();
+        match key {
+            KEY_ENTER         => { delwin(dialog); return Some(selected); },
+            KEY_ESC | KEY_F10 => { delwin(dialog); return None; },
+            KEY_UP            => { start_from -= 1; },
+            KEY_DOWN          => { start_from += 1; },
+            KEY_PPAGE         => { start_from -= 10; },
+            KEY_NPAGE         => { start_from += 10; },
+            KEY_LEFT | KEY_RIGHT | KEY_TAB => {
+                selected = if key == KEY_LEFT { selected + options.len() - 1 } else { selected + 1 } % options.len();
+                message = update(selected);
+                start_from = 0;
+            },
+            _ => { },
+        }
+    }
+}
+
+pub fn range_box(title: &str, prompt: &str, items: &[String], first: usize, last: usize,
+                 update: &mut dyn FnMut(usize, usize) -> Vec<String>) -> Option<(usize, usize)> {
+
+    let (max_y, max_x) = screen_dimensions();
+    let (height, width) = (max_y - 6, max_x - 8);
+    let (start_y, start_x) = center_window(height, width);
+    let footnote = "UP/DOWN Move bound  TAB Switch bound  ENTER Confirm  F10 Cancel";
+    let dialog = window(height, width, start_y, start_x, title);
+    let visible = height - 9;
+
+    mvwprintw(dialog, 2, 3, prompt);
+    write_footnote(dialog, height, width, footnote);
+
+    let (mut first, mut last) = (first, last);
+    let mut is_first_active = true;
+    let mut message = update(first, last);
+
+    loop {
+
+        let bounds = [("First", first, is_first_active), ("Last", last, !is_first_active)];
+        for (i, (label, index, is_active)) in bounds.iter().enumerate() {
+            let item = items.get(*index).map(|s| s.as_str()).unwrap_or("");
+            mvwprintw(dialog, 3 + i as i32, 3, &format!("{:<6}", label));
+            if *is_active { wattron(dialog, A_REVERSE()); }
+            mvwprintw(dialog, 3 + i as i32, 10, &fit_to_window(item, width as usize - 13));
+            wattroff(dialog, A_REVERSE());
+        }
+
+        for i in 0..visible {
+            let line = message.get(i as usize).map(|s| s.as_str()).unwrap_or("");
+            mvwprintw(dialog, 6 + i, 3, &fit_to_window(line, width as usize - 6));
+        }
+
+        refresh();
+        wrefresh(dialog);
+
+        let bound = if is_first_active { &mut first } else { &mut last };
+        let previous = *bound;
+
+        let key = getch();
+        match key {
+            KEY_ENTER         => { delwin(dialog); return Some((first, last)); },
+            KEY_ESC | KEY_F10 => { delwin(dialog); return None; },
+            KEY_TAB           => { is_first_active = !is_first_active; },
+            KEY_UP            => { *bound = bound.saturating_sub(1); },
+            KEY_DOWN          => { *bound = (*bound + 1).min(items.len().saturating_sub(1)); },
+            _ => { },
+        }
+
+        if is_first_active && first > last { last = first; }
+        if !is_first_active && last < first { first = last; }
+
+        let current = if is_first_active { first } else { last };
+        if key != KEY_TAB && current != previous {
+            message = update(first, last);
+        }
+    }
+}
+
+pub fn menu_box(title: &str, prompt: &str, items: Vec<String>) -> Option<usize> {
+
+    let (max_y, _) = screen_dimensions();
+    let longest = items.iter().map(|s| s.len()).max().unwrap_or(0) as i32;
+    let (height, width) = ((MIN_HEIGHT + items.len() as i32).min(max_y - 2), DEFAULT_WIDTH.max(longest + 8));
+    let (start_y, start_x) = center_window(height, width);
+    let footnote = "ENTER Select   F10 cancel";
+    let dialog = window(height, width, start_y, start_x, title);
+    let visible = height - MIN_HEIGHT;
+
+    mvwprintw(dialog, 2, 3, prompt);
+    write_footnote(dialog, height, width, footnote);
+
+    let mut position: i32 = 0;
+    let mut start_from: i32 = 0;
+
+    loop {
+
+        if position < start_from            { start_from = position; }
+        if position >= start_from + visible { start_from = position - visible + 1; }
+
+        for i in 0..visible {
+            let index = i + start_from;
+            let item = items.get(index as usize).map(|s| s.as_str()).unwrap_or("");
+
+            if index == position { wattron(dialog, A_REVERSE()); }
+            mvwprintw(dialog, 3 + i, 3, &fit_to_window(item, width as usize - 6));
+            wattroff(dialog, A_REVERSE());
+        }
+
+        refresh();
+        wrefresh(dialog);
+
+        let key = getch();
+        match key {
+            KEY_ENTER         => { delwin(dialog); return Some(position as usize).filter(|_| !items.is_empty()); },
+            KEY_ESC | KEY_F10 => { delwin(dialog); return None; },
+            KEY_UP            => { position -= 1; },
+            KEY_DOWN          => { position += 1; },
+            KEY_PPAGE         => { position -= 10; },
+            KEY_NPAGE         => { position += 10; },
+            _ => { },
+        }
+
+        position = position.clamp(0, (items.len() as i32 - 1).max(0));
+    }
+}
+
+pub fn file_box(title: &str, directory: &str) -> Option<String> {
+
+    let mut directory = Path::new(directory).to_path_buf();
+
+    loop {
+
+        let mut entries = fs::read_dir(&directory)
+            .map(|entries| entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| (entry.path().is_dir(), entry.file_name().to_string_lossy().to_string()))
+                .collect::<Vec<(bool, String)>>())
+            .unwrap_or_default();
+        entries.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        let mut items = vec![String::from("../")];
+        items.extend(entries.iter().map(|(is_dir, name)| if *is_dir { format!("{}/", name) } else { name.clone() }));
+
+        let prompt = format!("Select a file in {}:", directory.display());
+        let item = &items[menu_box(title, &prompt, items.clone())?];
+
+        if item == "../" {
+            if let Some(parent) = directory.parent() { directory = parent.to_path_buf(); }
+        } else if let Some(name) = item.strip_suffix('/') {
+            directory.push(name);
+        } else {
+            return Some(directory.join(item).to_string_lossy().to_string());
+        }
+    }
+}
+
 pub fn confirmation_box(title: &str, prompt: &str, message: Vec<String>) -> bool {
 
     let (height, width) = (MIN_HEIGHT + message.len() as i32, DEFAULT_WIDTH);
@@ -239,8 +609,46 @@ pub fn confirmation_box(title: &str, prompt: &str, message: Vec<String>) -> bool
     let key = getch();
     delwin(dialog);
 
-    if key == KEY_ENTER { true  }
-    else                { false }
+    key == KEY_ENTER
+}
+
+pub fn typed_confirmation_box(title: &str, prompt: &str, message: Vec<String>, expected: &str) -> bool {
+
+    let (height, width) = (MIN_HEIGHT + message.len() as i32 + 2, DEFAULT_WIDTH);
+    let (start_y, start_x) = center_window(height, width);
+    let footnote = "ENTER Confirm   F10 cancel";
+    let dialog = window(height, width, start_y, start_x, title);
+    let input_y = 4 + message.len() as i32;
+
+    mvwprintw(dialog, 2, 3, prompt);
+    for (i, value) in message.iter().enumerate() {
+        mvwprintw(dialog, 3 + i as i32, 3, value);
+    }
+    mvwprintw(dialog, input_y - 1, 3, &format!("Type '{}' to confirm:", expected));
+    write_footnote(dialog, height, width, footnote);
+
+    let mut input = String::new();
+
+    loop {
+
+        let input_str = format!("{} ", input);
+        wattron(dialog, A_REVERSE());
+        mvwprintw(dialog, input_y, 3, SPACE);
+        mvwprintw(dialog, input_y, 3, &input_str);
+        wattroff(dialog, A_REVERSE());
+
+        refresh();
+        wrefresh(dialog);
+
+        let key = getch();
+        match key {
+            KEY_ENTER         => { delwin(dialog); return input == expected; },
+            KEY_ESC | KEY_F10 => { delwin(dialog); return false; },
+            0x20..=0x7f       => { input.push(std::char::from_u32(key as u32).unwrap()); },
+            KEY_BACKSPACE     => { input.pop(); }
+            _                 => {},
+        }
+    }
 }
 
 pub fn message_box(title: &str, prompt: &str, message: Vec<String>) {
@@ -272,7 +680,7 @@ pub fn single_input_box(title: &str, prompt: &str, default_value: String) -> Str
 
     mvwprintw(dialog, 2, 3, prompt);
     write_footnote(dialog, height, width, footnote);
-    let mut input = format!("{}", default_value);
+    let mut input = default_value.to_string();
 
     loop {
 
@@ -288,8 +696,8 @@ pub fn single_input_box(title: &str, prompt: &str, default_value: String) -> Str
         let key = getch();
         match key {
             KEY_ENTER      => { delwin(dialog); return input; },
-            KEY_ESC        => { delwin(dialog); return format!(""); },
-            KEY_F10        => { delwin(dialog); return format!(""); },
+            KEY_ESC        => { delwin(dialog); return String::new(); },
+            KEY_F10        => { delwin(dialog); return String::new(); },
             0x20..=0x7f    => { input.push(std::char::from_u32(key as u32).unwrap()); },
             KEY_BACKSPACE  => { input.pop(); }
             _              => {},
@@ -306,8 +714,8 @@ pub fn dual_input_box(title: &str, prompt: &str, default_value1: String, default
 
     mvwprintw(dialog, 2, 3, prompt);
     write_footnote(dialog, height, width, footnote);
-    let mut input1 = format!("{}", default_value1);
-    let mut input2 = format!("{}", default_value2);
+    let mut input1 = default_value1.to_string();
+    let mut input2 = default_value2.to_string();
     let mut is_input1_selected = true;
 
     loop {
@@ -356,18 +764,85 @@ pub fn dual_input_box(title: &str, prompt: &str, default_value1: String, default
     }
 }
 
+pub enum Field {
+    Text(String, String),
+    Check(String, bool),
+}
+
+pub fn form_box(title: &str, prompt: &str, fields: &mut [Field]) -> bool {
+
+    let rows = fields.iter()
+        .scan(3, |y, field| {
+            let row = *y;
+            *y += match field { Field::Text(..) => 2, Field::Check(..) => 1 };
+            Some(row)
+        })
+        .collect::<Vec<i32>>();
+
+    let used = rows.last().map(|y| y + 2 - 3).unwrap_or(0);
+    let (height, width) = (MIN_HEIGHT + used, DEFAULT_WIDTH);
+    let (start_y, start_x) = center_window(height, width);
+    let footnote = "UP/DOWN Field  SPACE Toggle  ENTER Confirm  F10 cancel";
+    let dialog = window(height, width, start_y, start_x, title);
+
+    mvwprintw(dialog, 2, 3, prompt);
+    write_footnote(dialog, height, width, footnote);
+
+    let mut selected: usize = 0;
+
+    loop {
+
+        for (i, field) in fields.iter().enumerate() {
+            let y = rows[i];
+
+            match field {
+                Field::Text(label, value) => {
+                    mvwprintw(dialog, y, 3, &fit_to_window(&format!("{}:", label), width as usize - 6));
+                    if i == selected { wattron(dialog, A_REVERSE()); }
+                    mvwprintw(dialog, y + 1, 3, SPACE);
+                    mvwprintw(dialog, y + 1, 3, &format!("{} ", value));
+                    wattroff(dialog, A_REVERSE());
+                },
+                Field::Check(label, is_checked) => {
+                    let mark = if *is_checked { "X" } else { " " };
+                    if i == selected { wattron(dialog, A_REVERSE()); }
+                    mvwprintw(dialog, y, 3, &format!("[{}] {}", mark, label));
+                    wattroff(dialog, A_REVERSE());
+                },
+            }
+        }
+
+        refresh();
+        wrefresh(dialog);
+
+        let key = getch();
+        match (key, &mut fields[selected]) {
+            (KEY_ENTER, _)                  => { delwin(dialog); return true; },
+            (KEY_ESC, _) | (KEY_F10, _)     => { delwin(dialog); return false; },
+            (KEY_UP, _)                     => { selected = selected.saturating_sub(1); },
+            (KEY_DOWN, _) | (KEY_TAB, _)    => { selected = (selected + 1) % fields.len(); },
+            (KEY_SPACE, Field::Check(_, is_checked)) => { *is_checked = !*is_checked; },
+            (0x20..=0x7f, Field::Text(_, value)) => { value.push(std::char::from_u32(key as u32).unwrap()); },
+            (KEY_BACKSPACE, Field::Text(_, value)) => { value.pop(); },
+            _ => { },
+        }
+    }
+}
+
 fn bottom_menu(content_type: &ContentType) {
 
-    let pools     = String::from(" F1 Help  F2 ____  F3 ____  F4 ____  F5 ____  F6 ____  F7 Scrub  F8 Destroy  F9 Get all  F10 Exit");
+    let pools     = String::from(" F1 Help  F2 Status  F3 Iostat  F4 History  F5 Events  F6 Features  F7 Scrub  F8 Destroy  F9 Get all  F10 Exit  F11 Checkpoint  F12 More");
     let volumes   = String::from(" F1 Help  F2 ____  F3 ____  F4 ____  F5 Snapshot  F6 Rename  F7 Create  F8 Destroy  F9 Get all  F10 Exit");
-    let datasets  = String::from(" F1 Help  F2 Promote  F3 ____  F4 ____  F5 Snapshot  F6 Rename  F7 Create  F8 Destroy  F9 Get all  F10 Exit");
-    let snapshots = String::from(" F1 Help  F2 Diff  F3 Send  F4 ____  F5 Clone  F6 Rename  F7 Rollback  F8 Destroy  F9 Get all  F10 Exit");
+    let datasets  = String::from(" F1 Help  F2 Promote  F3 Receive  F4 ____  F5 Snapshot  F6 Rename  F7 Create  F8 Destroy  F9 Get all  F10 Exit");
+    let snapshots = String::from(" F1 Help  F2 Diff  F3 Send  F4 Holds  F5 Clone  F6 Rename  F7 Rollback  F8 Destroy  F9 Get all  F10 Exit  F11 Bookmark  F12 More");
+    let bookmarks = String::from(" F1 Help  F2 ____  F3 Send  F4 ____  F5 ____  F6 Rename  F7 ____  F8 Destroy  F9 Get all  F10 Exit");
 
     let mut menu = match content_type {
         ContentType::Pools =>     { pools     },
         ContentType::Datasets =>  { datasets  },
         ContentType::Volumes =>   { volumes   },
         ContentType::Snapshots => { snapshots },
+        ContentType::Bookmarks => { bookmarks },
     };
 
     let (height, width) = screen_dimensions();
@@ -375,6 +850,7 @@ fn bottom_menu(content_type: &ContentType) {
     for _ in menu.len()..(width as usize) {
         menu.push(' ');
     }
+    menu.truncate(width as usize - 1);
 
     mvprintw(height - 1, 0, &menu);
 }
@@ -387,11 +863,18 @@ fn write_at(pane: WINDOW, content: &Content, height: i32, width: i32) {
         if (i as i32) >= height + content.start { break }
         if (i as i32) == content.position && content.is_selected { wattron(pane, A_REVERSE()); }
 
-        let text = fit_to_window(line.as_str(), width as usize);
+        let is_marked = content.marked.contains(line);
+        let line = if is_marked { format!("*{}", line) } else { line.to_string() };
+        if is_marked { wattron(pane, A_BOLD()); }
+
+        let text = match content.details.get(i) {
+            Some(detail) if !detail.is_empty() => with_detail(&line, detail, width as usize),
+            _ => fit_to_window(line.as_str(), width as usize),
+        };
         let content_position = i as i32 - content.start + 1;
 
         mvwprintw(pane, content_position, 1, text.as_str());
-        wattroff(pane, A_REVERSE());
+        wattroff(pane, A_REVERSE() | A_BOLD());
     }  
 }
 
@@ -415,12 +898,37 @@ fn fit_to_window(source_line: &str, width: usize) -> String {
     }   
 
     for _ in line.len()..width-2 {
-        line.push_str(" ");
+        line.push(' ');
     }   
 
     line
 }
 
+fn with_detail(source_line: &str, detail: &str, width: usize) -> String {
+
+    let available = (width as i32 - 2) - (detail.len() as i32 + 1);
+
+    if available < 1 {
+        return fit_to_window(source_line, width);
+    }
+
+    let line = fit_to_window(source_line, available as usize + 2);
+    format!("{} {}", line, detail)
+}
+
+pub fn progress_bar(percent: f32, width: usize) -> String {
+
+    let percent = percent.clamp(0.0, 100.0);
+    let filled = ((percent / 100.0) * width as f32) as usize;
+
+    format!("[{}{}] {:5.1}%", "#".repeat(filled), ".".repeat(width - filled), percent)
+}
+
+fn function_key(key: i32) -> Option<i32> {
+
+    (1..=12).find(|n| key == KEY_F(*n as u8))
+}
+
 fn window(height: i32, width: i32, start_y: i32, start_x: i32, title: &str) -> WINDOW {
 
     let win = newwin(height, width, start_y, start_x);
@@ -1,10 +1,16 @@
 
-use crate::contents::{Content, ContentType};
+use std::time::{Duration, Instant};
+
+use crate::contents::{self, Content, ContentType};
 use crate::dialogs;
 
+const STATUS_INTERVAL: Duration = Duration::from_secs(30);
+
 pub struct Engine {
     left:  Content,
     right: Content,
+    status: Vec<String>,
+    status_time: Option<Instant>,
 }
 
 impl Engine {
@@ -17,6 +23,8 @@ impl Engine {
 
             left:  Content::new(true,  ContentType::Datasets),
             right: Content::new(false, ContentType::Snapshots),
+            status: Vec::new(),
+            status_time: None,
         }
     }
 
@@ -26,12 +34,26 @@ impl Engine {
 
             self.left.update();
             self.right.update();
+            self.update_status();
 
-            dialogs::dual_pane(&mut self.left, &mut self.right);
+            dialogs::dual_pane(&mut self.left, &mut self.right, &self.status);
             let is_to_finish = dialogs::handle_keys(&mut self.left, &mut self.right);
             if is_to_finish { break; }
         }
     }
+
+    fn update_status(&mut self) {
+
+        let is_expired = match self.status_time {
+            Some(time) => time.elapsed() > STATUS_INTERVAL,
+            None       => true,
+        };
+
+        if is_expired {
+            self.status = contents::pool_warnings();
+            self.status_time = Some(Instant::now());
+        }
+    }
 }
 
 impl Drop for Engine {
@@ -5,6 +5,8 @@ mod engine;
 mod contents;
 mod dialogs;
 mod commands;
+mod config;
+mod retention;
 
 fn main() {
 
@@ -20,7 +22,13 @@ fn main() {
         return;
     }
 
-    if is_zfs_installed() {
+    let is_daemon = args.contains(&String::from("--daemon"));
+    let is_auto_snapshot = args.contains(&String::from("auto-snapshot"));
+
+    if is_zfs_installed() && (is_daemon || is_auto_snapshot) {
+        retention::run(is_daemon);
+
+    } else if is_zfs_installed() {
         let mut engine = engine::Engine::new();
         engine.run();
 
@@ -43,23 +51,26 @@ r#"Navigation Keys:
 
 UP/DOWN/PG_UP/PG_DOWN: Navigate within current pane
 LEFT/RIGHT:            Change pane
+INSERT/SPACE:          Mark or unmark the current item
 TAB:                   Change current pane mode
 
 
 Function Keys:
 
-|  Key  |       Pool      |    Dataset    |    Snapshot   |     Volume    |
-|:-----:|:---------------:|:-------------:|:-------------:|:-------------:|
-|  F1   |       Help      |      Help     |      Help     |      Help     |
-|  F2   |         -       |        -      |    zfs diff   |        -      |
-|  F3   |         -       |        -      |    zfs send   |        -      |
-|  F4   |         -       |        -      |        -      |        -      |
-|  F5   |         -       |  zfs snapshot |   zfs clone   |  zfs snapshot |
-|  F6   |         -       |   zfs rename  |   zfs rename  |   zfs rename  |
-|  F7   |   zpool scrub   |   zfs create  |  zfs rollback |   zfs create  |
-|  F8   |  zpool destroy  |  zfs destroy  |  zfs destroy  |  zfs destroy  |
-|  F9   |  zpool get all  |  zfs get all  |  zfs get all  |  zfs get all  |
-|  F10  |       Exit      |      Exit     |      Exit     |      Exit     |
+|  Key  |       Pool      |    Dataset    |    Snapshot   |     Volume    |    Bookmark   |
+|:-----:|:---------------:|:-------------:|:-------------:|:-------------:|:-------------:|
+|  F1   |       Help      |      Help     |      Help     |      Help     |      Help     |
+|  F2   |   zpool status  |        -      |    zfs diff   |        -      |        -      |
+|  F3   |   zpool iostat  |    zfs recv   |    zfs send   |        -      |  zfs send -i  |
+|  F4   |  zpool history  |        -      |   zfs holds   |        -      |        -      |
+|  F5   |   zpool events  |  zfs snapshot |   zfs clone   |  zfs snapshot |        -      |
+|  F6   |  pool features  |   zfs rename  |   zfs rename  |   zfs rename  |   zfs rename  |
+|  F7   |   zpool scrub   |   zfs create  |  zfs rollback |   zfs create  |        -      |
+|  F8   |  zpool destroy  |  zfs destroy  |  zfs destroy  |  zfs destroy  |  zfs destroy  |
+|  F9   |  zpool get all  |  zfs get all  |  zfs get all  |  zfs get all  |  zfs get all  |
+|  F10  |       Exit      |      Exit     |      Exit     |      Exit     |      Exit     |
+|  F11  |    checkpoint   |        -      |  zfs bookmark |        -      |        -      |
+|  F12  |   more actions  |        -      |  more actions |        -      |        -      |
 "#;
 
 const LICENSE: &str = r#"
@@ -0,0 +1,537 @@
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::commands;
+use crate::config::Config;
+
+pub const CLASSES: [(&str, u64); 5] = [
+    ("frequent", 15 * 60),
+    ("hourly",   60 * 60),
+    ("daily",    24 * 60 * 60),
+    ("weekly",   7 * 24 * 60 * 60),
+    ("monthly",  30 * 24 * 60 * 60),
+];
+
+const PROPERTY_PREFIX: &str = "com.zc:";
+const DAEMON_INTERVAL: Duration = Duration::from_secs(60);
+const SCHEDULE_SLACK: u64 = 30;
+
+#[derive(Clone)]
+pub struct Policy {
+    pub dataset:      String,
+    pub counts:       [u64; 5],
+    pub is_recursive: bool,
+    pub prefix:       String,
+}
+
+pub struct Snapshot {
+    pub name:     String,
+    pub creation: u64,
+}
+
+impl Policy {
+
+    pub fn new(dataset: &str) -> Policy {
+        Policy {
+            dataset:      dataset.to_string(),
+            counts:       [0; 5],
+            is_recursive: false,
+            prefix:       String::from("zc"),
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+
+        match key {
+            "recursive" => { self.is_recursive = value == "true" || value == "on"; },
+            "prefix"    => { self.prefix = value.to_string(); },
+            _ => {
+                if let Some(i) = CLASSES.iter().position(|(class, _)| *class == key) {
+                    self.counts[i] = value.parse().unwrap_or(0);
+                }
+            },
+        }
+    }
+
+    fn parse(line: &str) -> Option<Policy> {
+
+        let mut words = line.split_whitespace();
+        let mut policy = Policy::new(words.next()?);
+
+        for (key, value) in words.filter_map(|word| word.split_once('=')) {
+            policy.set(key, value);
+        }
+
+        Some(policy)
+    }
+
+    pub fn class_prefix(&self, class: usize) -> String {
+        format!("{}_{}-", self.prefix, CLASSES[class].0)
+    }
+
+    pub fn class_of(&self, snapshot: &str) -> Option<usize> {
+
+        let short = snapshot.split_once('@').map(|(_, s)| s).unwrap_or(snapshot);
+        (0..CLASSES.len()).find(|class| short.starts_with(&self.class_prefix(*class)))
+    }
+
+    pub fn describe(&self) -> String {
+
+        let counts = CLASSES.iter()
+            .zip(self.counts.iter())
+            .filter(|(_, count)| **count > 0)
+            .map(|((class, _), count)| format!("{}={}", class, count))
+            .collect::<Vec<String>>();
+
+        format!("{} {} recursive={} prefix={}", self.dataset, counts.join(" "), self.is_recursive, self.prefix)
+    }
+}
+
+pub struct AutoName {
+    pub class:     String,
+    pub timestamp: String,
+}
+
+fn timestamp(digits: &str) -> Option<String> {
+
+    let digits = digits.chars().filter(|c| c.is_ascii_digit()).collect::<String>();
+    if digits.len() < 12 { return None; }
+
+    Some(format!("{}-{}-{} {}:{}", &digits[0..4], &digits[4..6], &digits[6..8], &digits[8..10], &digits[10..12]))
+}
+
+pub fn prefixes(policies: &[Policy]) -> Vec<String> {
+
+    let mut prefixes = vec![Policy::new("").prefix];
+    for policy in policies.iter() {
+        if !prefixes.contains(&policy.prefix) { prefixes.push(policy.prefix.clone()); }
+    }
+
+    prefixes
+}
+
+pub fn parse_name(snapshot: &str, prefixes: &[String]) -> Option<AutoName> {
+
+    let short = snapshot.split_once('@').map(|(_, s)| s).unwrap_or(snapshot);
+
+    for prefix in prefixes.iter() {
+        let own = short.strip_prefix(prefix.as_str())
+            .and_then(|rest| rest.strip_prefix('_'))
+            .and_then(|rest| rest.split_once('-'))
+            .filter(|(class, _)| CLASSES.iter().any(|(name, _)| name == class));
+
+        if let Some((class, date)) = own {
+            return Some(AutoName { class: class.to_string(), timestamp: timestamp(date)? });
+        }
+    }
+
+    if let Some(rest) = short.strip_prefix("zfs-auto-snap_") {
+        let (class, date) = rest.split_once('-')?;
+        return Some(AutoName { class: class.to_string(), timestamp: timestamp(date)? });
+    }
+
+    if let Some(rest) = short.strip_prefix("autosnap_") {
+        let (date, class) = rest.rsplit_once('_')?;
+        let class = if class == "frequently" { "frequent" } else { class };
+        return Some(AutoName { class: class.to_string(), timestamp: timestamp(date)? });
+    }
+
+    let is_znapzend = short.len() == 17 && short.chars().all(|c| c.is_ascii_digit() || c == '-');
+    if is_znapzend {
+        return Some(AutoName { class: String::from("znapzend"), timestamp: timestamp(short)? });
+    }
+
+    None
+}
+
+pub fn policies() -> Vec<Policy> {
+
+    let keys = ["auto-snapshot", "recursive", "prefix"].iter()
+        .chain(CLASSES.iter().map(|(class, _)| class))
+        .map(|key| format!("{}{}", PROPERTY_PREFIX, key))
+        .collect::<Vec<String>>()
+        .join(",");
+
+    let arguments = vec!["get", "-H", "-s", "local,received", "-o", "name,property,value", "-t", "filesystem,volume", &keys];
+    let result = commands::list("zfs", &arguments);
+
+    merge(&result, &Config::load().policies)
+}
+
+fn merge(properties: &[String], config_policies: &[String]) -> Vec<Policy> {
+
+    let mut policies: Vec<Policy> = Vec::new();
+
+    for fields in properties.iter().map(|line| line.split('\t').collect::<Vec<&str>>()) {
+        if fields.len() < 3 { continue; }
+
+        let key = fields[1].trim_start_matches(PROPERTY_PREFIX);
+
+        if !policies.iter().any(|p| p.dataset == fields[0]) {
+            policies.push(Policy::new(fields[0]));
+        }
+        if let Some(policy) = policies.iter_mut().find(|p| p.dataset == fields[0]) {
+            policy.set(key, fields[2]);
+        }
+    }
+
+    let is_disabled = |dataset: &str| {
+        properties.iter().any(|line| *line == format!("{}\t{}auto-snapshot\tfalse", dataset, PROPERTY_PREFIX))
+    };
+    policies.retain(|policy| !is_disabled(&policy.dataset));
+
+    for policy in config_policies.iter().filter_map(|line| Policy::parse(line)) {
+        policies.retain(|p| p.dataset != policy.dataset);
+        policies.push(policy);
+    }
+
+    policies.retain(|policy| policy.counts.iter().any(|count| *count > 0));
+    policies
+}
+
+pub fn dataset_snapshots(dataset: &str) -> Vec<Snapshot> {
+
+    let arguments = vec!["list", "-H", "-p", "-o", "name,creation", "-t", "snapshot", "-s", "creation", "-d", "1", dataset];
+
+    commands::list("zfs", &arguments)
+        .iter()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(name, creation)| Snapshot { name: name.to_string(), creation: creation.parse().unwrap_or(0) })
+        .collect()
+}
+
+pub fn expired(policy: &Policy, snapshots: &[Snapshot]) -> Vec<String> {
+
+    let mut expired = Vec::new();
+
+    for (class, count) in policy.counts.iter().enumerate() {
+        let mut of_class = snapshots.iter()
+            .filter(|snapshot| policy.class_of(&snapshot.name) == Some(class))
+            .collect::<Vec<&Snapshot>>();
+        of_class.sort_by_key(|snapshot| snapshot.creation);
+
+        let excess = of_class.len().saturating_sub(*count as usize);
+        expired.extend(of_class.iter().take(excess).map(|snapshot| snapshot.name.clone()));
+    }
+
+    expired
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn log(message: &str, is_verbose: bool) {
+    if is_verbose { println!("{}", message); }
+    commands::log(&format!("auto-snapshot: {}", message));
+}
+
+pub fn prune(policy: &Policy, is_verbose: bool) {
+
+    let flag = if policy.is_recursive { "-r" } else { "" };
+
+    for snapshot in expired(policy, &dataset_snapshots(&policy.dataset)) {
+        let result = commands::zfs_destroy(&snapshot, flag);
+
+        if result.is_empty() {
+            log(&format!("snapshot {} pruned", snapshot), is_verbose);
+        } else {
+            log(&format!("pruning {} failed: {}", snapshot, result.join(" ")), is_verbose);
+        }
+    }
+}
+
+pub fn classify(policies: &[Policy], snapshots: &[String]) -> Vec<String> {
+
+    let mut labels = vec![String::new(); snapshots.len()];
+
+    for policy in policies.iter() {
+        let expired = expired(policy, &dataset_snapshots(&policy.dataset))
+            .iter()
+            .filter_map(|name| name.split_once('@').map(|(_, short)| short.to_string()))
+            .collect::<Vec<String>>();
+
+        for (i, snapshot) in snapshots.iter().enumerate() {
+            let (dataset, short) = match snapshot.split_once('@') {
+                Some(parts) => parts,
+                None        => continue,
+            };
+
+            let is_covered = dataset == policy.dataset
+                || (policy.is_recursive && dataset.starts_with(&format!("{}/", policy.dataset)));
+
+            if let Some(class) = policy.class_of(snapshot).filter(|_| is_covered) {
+                let action = if expired.iter().any(|name| name == short) { "prune" } else { "keep" };
+                labels[i] = format!("{} {}", CLASSES[class].0, action);
+            }
+        }
+    }
+
+    labels
+}
+
+fn apply(policy: &Policy, now: u64, is_verbose: bool) {
+
+    let snapshots = dataset_snapshots(&policy.dataset);
+
+    for (class, (name, period)) in CLASSES.iter().enumerate() {
+        if policy.counts[class] == 0 { continue; }
+
+        let latest = snapshots.iter()
+            .filter(|snapshot| policy.class_of(&snapshot.name) == Some(class))
+            .map(|snapshot| snapshot.creation)
+            .max()
+            .unwrap_or(0);
+
+        if now + SCHEDULE_SLACK < latest + period { continue; }
+
+        let template = format!("{}%Y-%m-%d-%H%M", policy.class_prefix(class));
+        let new_snapshot = format!("{}@{}", policy.dataset, commands::expand_template(&template));
+        let result = commands::zfs_snapshot(&new_snapshot, policy.is_recursive, &[]);
+
+        if result.is_empty() {
+            log(&format!("{} snapshot {} taken", name, new_snapshot), is_verbose);
+        } else {
+            log(&format!("{} snapshot {} failed: {}", name, new_snapshot, result.join(" ")), is_verbose);
+        }
+    }
+
+    prune(policy, is_verbose);
+}
+
+pub fn run(is_daemon: bool) {
+
+    let mut described: Option<Vec<String>> = None;
+
+    loop {
+
+        let policies = policies();
+
+        let descriptions = policies.iter().map(|policy| policy.describe()).collect::<Vec<String>>();
+        if described.as_ref() != Some(&descriptions) {
+            if policies.is_empty() {
+                log("no retention policies configured", true);
+            }
+            for description in descriptions.iter() {
+                log(&format!("policy {}", description), true);
+            }
+            described = Some(descriptions);
+        }
+
+        for policy in policies.iter() {
+            apply(policy, now(), true);
+        }
+
+        if !is_daemon { break; }
+        thread::sleep(DAEMON_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn snapshot(name: &str, creation: u64) -> Snapshot {
+        Snapshot { name: name.to_string(), creation }
+    }
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_reads_dataset_counts_and_options() {
+        let policy = Policy::parse("tank/data hourly=24 daily=7 monthly=12 recursive=true prefix=auto").unwrap();
+
+        assert_eq!(policy.dataset, "tank/data");
+        assert_eq!(policy.counts, [0, 24, 7, 0, 12]);
+        assert!(policy.is_recursive);
+        assert_eq!(policy.prefix, "auto");
+    }
+
+    #[test]
+    fn parse_defaults_and_ignores_unknown_keys() {
+        let policy = Policy::parse("tank daily=3 yearly=2 junk").unwrap();
+
+        assert_eq!(policy.counts, [0, 0, 3, 0, 0]);
+        assert!(!policy.is_recursive);
+        assert_eq!(policy.prefix, "zc");
+        assert!(Policy::parse("   ").is_none());
+    }
+
+    #[test]
+    fn set_overrides_previous_values() {
+        let mut policy = Policy::new("tank");
+        policy.set("hourly", "24");
+        policy.set("hourly", "12");
+        policy.set("weekly", "many");
+        policy.set("recursive", "on");
+
+        assert_eq!(policy.counts, [0, 12, 0, 0, 0]);
+        assert!(policy.is_recursive);
+
+        policy.set("recursive", "off");
+        assert!(!policy.is_recursive);
+    }
+
+    #[test]
+    fn expired_keeps_the_newest_of_each_class() {
+        let policy = Policy::parse("tank hourly=2 daily=1").unwrap();
+        let snapshots = vec![
+            snapshot("tank@zc_hourly-2024-01-01-0100", 1),
+            snapshot("tank@zc_daily-2024-01-01-0000",  2),
+            snapshot("tank@zc_hourly-2024-01-01-0200", 3),
+            snapshot("tank@zc_daily-2024-01-02-0000",  4),
+            snapshot("tank@zc_hourly-2024-01-01-0300", 5),
+        ];
+
+        assert_eq!(expired(&policy, &snapshots), vec![
+            String::from("tank@zc_hourly-2024-01-01-0100"),
+            String::from("tank@zc_daily-2024-01-01-0000"),
+        ]);
+    }
+
+    #[test]
+    fn expired_prunes_oldest_first_regardless_of_order() {
+        let policy = Policy::parse("tank hourly=1").unwrap();
+        let snapshots = vec![
+            snapshot("tank@zc_hourly-c", 30),
+            snapshot("tank@zc_hourly-a", 10),
+            snapshot("tank@zc_hourly-b", 20),
+        ];
+
+        assert_eq!(expired(&policy, &snapshots), vec![String::from("tank@zc_hourly-a"), String::from("tank@zc_hourly-b")]);
+    }
+
+    #[test]
+    fn expired_keeps_snapshots_not_matching_the_policy() {
+        let policy = Policy::parse("tank hourly=1 prefix=auto").unwrap();
+        let snapshots = vec![
+            snapshot("tank@manual-20240101", 1),
+            snapshot("tank@zc_hourly-2024-01-01-0100", 2),
+            snapshot("tank@zfs-auto-snap_hourly-2024-01-01-0100", 3),
+            snapshot("tank@auto_hourlyish-2024-01-01-0100", 4),
+            snapshot("tank@auto_hourly-2024-01-01-0100", 5),
+            snapshot("tank@auto_hourly-2024-01-01-0200", 6),
+        ];
+
+        assert_eq!(expired(&policy, &snapshots), vec![String::from("tank@auto_hourly-2024-01-01-0100")]);
+    }
+
+    #[test]
+    fn expired_is_empty_within_the_counts() {
+        let policy = Policy::parse("tank hourly=5").unwrap();
+        let snapshots = vec![snapshot("tank@zc_hourly-a", 1), snapshot("tank@zc_hourly-b", 2)];
+
+        assert!(expired(&policy, &snapshots).is_empty());
+    }
+
+    fn name(snapshot: &str, prefixes: &[&str]) -> Option<(String, String)> {
+        parse_name(snapshot, &lines(prefixes)).map(|auto| (auto.class, auto.timestamp))
+    }
+
+    fn auto(class: &str, timestamp: &str) -> Option<(String, String)> {
+        Some((class.to_string(), timestamp.to_string()))
+    }
+
+    #[test]
+    fn parse_name_reads_zc_names() {
+        assert_eq!(name("tank@zc_hourly-2024-01-02-0300", &["zc"]), auto("hourly", "2024-01-02 03:00"));
+        assert_eq!(name("zc_frequent-2024-01-02-0315", &["zc"]), auto("frequent", "2024-01-02 03:15"));
+        assert_eq!(name("tank@zc_yearly-2024-01-02-0300", &["zc"]), None);
+        assert_eq!(name("tank@zc_hourly-20240102", &["zc"]), None);
+    }
+
+    #[test]
+    fn parse_name_reads_custom_prefixes() {
+        let prefixes = ["zc", "nightly"];
+
+        assert_eq!(name("tank@nightly_daily-2024-01-02-0000", &prefixes), auto("daily", "2024-01-02 00:00"));
+        assert_eq!(name("tank@nightly_daily-2024-01-02-0000", &["zc"]), None);
+        assert_eq!(name("tank@nightlydaily-2024-01-02-0000", &prefixes), None);
+    }
+
+    #[test]
+    fn parse_name_reads_zfs_auto_snapshot_names() {
+        assert_eq!(name("tank@zfs-auto-snap_hourly-2024-01-02-0300", &["zc"]), auto("hourly", "2024-01-02 03:00"));
+        assert_eq!(name("tank@zfs-auto-snap_frequent-2024-01-02-0315", &["zc"]), auto("frequent", "2024-01-02 03:15"));
+    }
+
+    #[test]
+    fn parse_name_reads_sanoid_names() {
+        assert_eq!(name("tank@autosnap_2024-01-02_03:00:01_hourly", &["zc"]), auto("hourly", "2024-01-02 03:00"));
+        assert_eq!(name("tank@autosnap_2024-01-02_03:15:01_frequently", &["zc"]), auto("frequent", "2024-01-02 03:15"));
+        assert_eq!(name("tank@autosnap_2024-01-01_00:00:01_yearly", &["zc"]), auto("yearly", "2024-01-01 00:00"));
+    }
+
+    #[test]
+    fn parse_name_reads_znapzend_names() {
+        assert_eq!(name("tank@2024-01-02-030000", &["zc"]), auto("znapzend", "2024-01-02 03:00"));
+        assert_eq!(name("tank@2024-01-02-0300", &["zc"]), None);
+    }
+
+    #[test]
+    fn parse_name_keeps_sanoid_names_apart_from_similar_prefixes() {
+        let sanoid = "tank@autosnap_2024-01-02_03:00:01_daily";
+
+        assert_eq!(name(sanoid, &["zc", "auto"]), auto("daily", "2024-01-02 03:00"));
+        assert_eq!(name(sanoid, &["zc", "autosnap"]), auto("daily", "2024-01-02 03:00"));
+        assert_eq!(name("tank@autosnap_hourly-2024-01-02-0300", &["zc", "autosnap"]), auto("hourly", "2024-01-02 03:00"));
+        assert_eq!(name("tank@auto_hourly-2024-01-02-0300", &["zc", "autosnap"]), None);
+    }
+
+    #[test]
+    fn parse_name_leaves_manual_names_alone() {
+        assert_eq!(name("tank@manual-20240102-0300", &["zc"]), None);
+        assert_eq!(name("tank@before-upgrade", &["zc"]), None);
+    }
+
+    #[test]
+    fn merge_reads_policies_from_properties() {
+        let properties = lines(&[
+            "tank/a\tcom.zc:hourly\t24",
+            "tank/a\tcom.zc:recursive\ttrue",
+            "tank/b\tcom.zc:prefix\tauto",
+            "tank/b\tcom.zc:daily\t7",
+        ]);
+        let policies = merge(&properties, &[]);
+
+        assert_eq!(policies.len(), 2);
+        assert_eq!(policies[0].dataset, "tank/a");
+        assert_eq!(policies[0].counts, [0, 24, 0, 0, 0]);
+        assert!(policies[0].is_recursive);
+        assert_eq!(policies[1].prefix, "auto");
+        assert_eq!(policies[1].counts, [0, 0, 7, 0, 0]);
+    }
+
+    #[test]
+    fn merge_lets_config_override_properties() {
+        let properties = lines(&[
+            "tank/a\tcom.zc:hourly\t24",
+            "tank/a\tcom.zc:recursive\ttrue",
+            "tank/b\tcom.zc:daily\t7",
+        ]);
+        let config = lines(&["tank/a daily=3", "tank/c weekly=4"]);
+        let policies = merge(&properties, &config);
+
+        let datasets = policies.iter().map(|policy| policy.dataset.as_str()).collect::<Vec<&str>>();
+        assert_eq!(datasets, vec!["tank/b", "tank/a", "tank/c"]);
+
+        let overridden = &policies[1];
+        assert_eq!(overridden.counts, [0, 0, 3, 0, 0]);
+        assert!(!overridden.is_recursive);
+    }
+
+    #[test]
+    fn merge_skips_disabled_and_empty_policies() {
+        let properties = lines(&[
+            "tank/a\tcom.zc:hourly\t24",
+            "tank/a\tcom.zc:auto-snapshot\tfalse",
+            "tank/b\tcom.zc:prefix\tauto",
+        ]);
+        let config = lines(&["tank/c recursive=true"]);
+
+        assert!(merge(&properties, &config).is_empty());
+    }
+}